@@ -0,0 +1,17 @@
+// {CONTEST} {PROBLEM} - {TITLE}
+// {URL}
+// {TIME_LIMIT} / {MEMORY_LIMIT}
+// author: {AUTHOR} ({DATE})
+#include <bits/stdc++.h>
+using namespace std;
+
+void solve() {}
+
+int main() {
+    cin.tie(nullptr);
+    ios::sync_with_stdio(false);
+
+    int t;
+    cin >> t;
+    while (t--) solve();
+}
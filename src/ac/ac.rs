@@ -1,17 +1,24 @@
-use std::error::Error;
+#![allow(clippy::needless_return)]
 
-use ac_tools_rs::Message;
-use clap::{Parser, Subcommand};
+use std::{error::Error, process};
+
+use ac_tools_rs::{log, Message};
+use clap::{builder::RangedU64ValueParser, Parser, Subcommand};
 
 mod debug;
 mod gen;
 mod init;
+mod library;
+mod list;
 mod nodebug;
 mod precompile;
+mod sample;
 mod service;
+mod show;
 mod submit;
 mod test;
 mod testcase;
+mod watch;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -21,6 +28,10 @@ mod testcase;
 struct Cli {
     #[clap(subcommand)]
     command: Commands,
+
+    /// 詳細なログを記録し、標準エラー出力にも表示する
+    #[arg(short = 'v', long = "verbose", global = true, action)]
+    verbose: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -42,16 +53,82 @@ enum Commands {
 
         #[arg(short = 'd', long = "debug", action)]
         debug: bool,
+
+        /// ソースや引数が変わっていなくてもコンパイルし直す
+        #[arg(long = "rebuild", action)]
+        rebuild: bool,
+
+        /// 同時に実行するテストケースの数(実行時間を正確に測る場合は1)
+        #[arg(short = 'j', long = "jobs", value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
     },
 
     #[clap(visible_alias("g"))]
-    gen {},
+    gen {
+        #[command(subcommand)]
+        target: Option<gen::Target>,
+
+        /// 開始時刻まで待機し、開始と同時に全ての問題のサンプルをダウンロードする
+        #[arg(short = 'w', long = "wait", global = true, action)]
+        wait: bool,
+
+        /// 生成後すぐに全ての問題のサンプルを並列にダウンロードする
+        #[arg(short = 'd', long = "download", global = true, action)]
+        download: bool,
+
+        /// コンテストの構成や上書きの確認を省略する
+        #[arg(short = 'y', long = "yes", global = true, action)]
+        yes: bool,
+
+        /// 指定した問題のファイルをテンプレートに戻す(変更されていれば退避する)
+        #[arg(short = 'r', long = "reset", value_name = "PROBLEM")]
+        reset: Option<String>,
+    },
+
+    /// コンテスト環境内の問題のファイルを監視し、保存するたびにサンプルでテストする
+    #[clap(visible_alias("w"))]
+    watch {
+        problem: String,
+
+        /// 初めて全てのサンプルに正解したときに自動で提出する
+        #[arg(short = 'a', long = "auto", action)]
+        auto: bool,
+
+        #[arg(short = 'd', long = "debug", action)]
+        debug: bool,
+
+        /// 同時に実行するテストケースの数(実行時間を正確に測る場合は1)
+        #[arg(short = 'j', long = "jobs", value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
+    },
 
     #[clap(visible_alias("p"))]
-    precompile {},
+    precompile {
+        /// 変更されていないヘッダも含めて全てプリコンパイルし直す
+        #[arg(short = 'f', long = "force", action)]
+        force: bool,
 
+        /// 確認を省略する
+        #[arg(short = 'y', long = "yes", action)]
+        yes: bool,
+    },
+
+    /// テストケースの追加・削除・一覧・編集(追加したケースはcustom-N.in/outとして保存される)
     #[clap(visible_alias("m"))]
-    testcase {},
+    testcase {
+        #[command(subcommand)]
+        command: testcase::Command,
+    },
+
+    /// コンテスト環境内の問題の一覧を制限や難易度とともに表示する
+    #[clap(visible_alias("l"))]
+    list {},
+
+    /// ライブラリ(AC_BASE_PATH/library)の検索と問題のファイルへの追加
+    lib {
+        #[command(subcommand)]
+        command: library::Command,
+    },
 
     #[clap(visible_alias("s"))]
     submit {
@@ -59,11 +136,59 @@ enum Commands {
         oj: bool,
     },
 
+    #[clap(
+        visible_alias("v"),
+        override_usage("ac show(v) [OPTIONS] <問題ID/URL>")
+    )]
+    show {
+        problem: String,
+
+        /// 英語の問題文を表示する(AtCoderのみ)
+        #[arg(short = 'e', long = "en", action)]
+        en: bool,
+
+        /// キャッシュを使わずに問題文を取得し直す
+        #[arg(short = 'r', long = "refresh", action)]
+        refresh: bool,
+    },
+
     #[clap(visible_alias("d"))]
-    debug { file_name: String },
+    debug {
+        file_name: String,
+
+        /// 標準入力の代わりに渡す入力(数字ならコンテスト環境内の問題のsample-N.in、それ以外はファイル)
+        #[arg(short = 'i', long = "input", value_name = "N|FILE")]
+        input: Option<String>,
+
+        /// 全てのサンプルを実行し、出力を期待される出力と並べて表示する
+        #[arg(short = 'a', long = "all", action, conflicts_with = "input")]
+        all: bool,
+
+        /// gdb上で実行する代わりに、以前の方式でコアダンプを読み込む
+        #[arg(long = "core", action, conflicts_with_all = ["input", "all"])]
+        core: bool,
+
+        /// ソースや引数が変わっていなくてもコンパイルし直す
+        #[arg(long = "rebuild", action)]
+        rebuild: bool,
+    },
 
     #[clap(visible_alias("n"))]
-    nodebug { file_name: String },
+    nodebug {
+        file_name: String,
+
+        /// 標準入力の代わりに渡す入力(数字ならコンテスト環境内の問題のsample-N.in、それ以外はファイル)
+        #[arg(short = 'i', long = "input", value_name = "N|FILE")]
+        input: Option<String>,
+
+        /// 全てのサンプルを実行し、出力を期待される出力と並べて表示する
+        #[arg(short = 'a', long = "all", action, conflicts_with = "input")]
+        all: bool,
+
+        /// ソースや引数が変わっていなくてもコンパイルし直す
+        #[arg(long = "rebuild", action)]
+        rebuild: bool,
+    },
 
     #[clap(hide = true)]
     __init {
@@ -76,7 +201,9 @@ enum Commands {
 
     #[clap(
         hide = true,
-        override_usage("<問題ID> [OPTIONS]\n利用可能な問題ID: a, b, c, d, e, f, g, h")
+        override_usage(
+            "<問題ID> [OPTIONS]\n利用可能な問題ID: コンテスト環境内の<問題ID>.cppのファイル名"
+        )
     )]
     __contest {
         #[arg(hide = true)]
@@ -87,6 +214,14 @@ enum Commands {
 
         #[arg(short = 'd', long = "debug", action)]
         debug: bool,
+
+        /// ソースや引数が変わっていなくてもコンパイルし直す
+        #[arg(long = "rebuild", action)]
+        rebuild: bool,
+
+        /// 同時に実行するテストケースの数(実行時間を正確に測る場合は1)
+        #[arg(short = 'j', long = "jobs", value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
     },
 
     #[clap(
@@ -104,6 +239,14 @@ enum Commands {
 
         #[arg(short = 'd', long = "debug", action)]
         debug: bool,
+
+        /// ソースや引数が変わっていなくてもコンパイルし直す
+        #[arg(long = "rebuild", action)]
+        rebuild: bool,
+
+        /// 同時に実行するテストケースの数(実行時間を正確に測る場合は1)
+        #[arg(short = 'j', long = "jobs", value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
     },
 
     #[clap(hide = true, override_usage("cf [OPTIONS] <コンテストID> <問題ID>"))]
@@ -116,6 +259,14 @@ enum Commands {
 
         #[arg(short = 'd', long = "debug", action)]
         debug: bool,
+
+        /// ソースや引数が変わっていなくてもコンパイルし直す
+        #[arg(long = "rebuild", action)]
+        rebuild: bool,
+
+        /// 同時に実行するテストケースの数(実行時間を正確に測る場合は1)
+        #[arg(short = 'j', long = "jobs", value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
     },
 
     #[clap(hide = true, override_usage("yk [OPTIONS] <問題ID>"))]
@@ -127,11 +278,20 @@ enum Commands {
 
         #[arg(short = 'd', long = "debug", action)]
         debug: bool,
+
+        /// ソースや引数が変わっていなくてもコンパイルし直す
+        #[arg(long = "rebuild", action)]
+        rebuild: bool,
+
+        /// 同時に実行するテストケースの数(実行時間を正確に測る場合は1)
+        #[arg(short = 'j', long = "jobs", value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
     },
 }
 
 fn main() {
     let args = Cli::parse();
+    log::set_verbose(args.verbose);
 
     let mut err: Option<Box<dyn Error>> = None;
     match args.command {
@@ -141,25 +301,64 @@ fn main() {
             custom,
             auto,
             debug,
-        } => match test::run(arg1, arg2, custom, auto, debug) {
+            rebuild,
+            jobs,
+        } => match test::run(arg1, arg2, custom, auto, debug, rebuild, jobs) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
             }
         },
-        Commands::gen {} => match gen::run() {
+        Commands::gen {
+            target,
+            wait,
+            download,
+            yes,
+            reset,
+        } => match gen::run(
+            target,
+            gen::Options {
+                wait,
+                download,
+                yes,
+            },
+            reset,
+        ) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
             }
         },
-        Commands::precompile {} => match precompile::run() {
+        Commands::watch {
+            problem,
+            auto,
+            debug,
+            jobs,
+        } => match watch::run(problem, auto, debug, jobs) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
             }
         },
-        Commands::testcase {} => match testcase::run() {
+        Commands::precompile { force, yes } => match precompile::run(force, yes) {
+            Ok(_) => {}
+            Err(error) => {
+                err = Some(error);
+            }
+        },
+        Commands::testcase { command } => match testcase::run(command) {
+            Ok(_) => {}
+            Err(error) => {
+                err = Some(error);
+            }
+        },
+        Commands::list {} => match list::run() {
+            Ok(_) => {}
+            Err(error) => {
+                err = Some(error);
+            }
+        },
+        Commands::lib { command } => match library::run(command) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
@@ -171,13 +370,34 @@ fn main() {
                 err = Some(error);
             }
         },
-        Commands::debug { file_name } => match debug::run(file_name) {
+        Commands::show {
+            problem,
+            en,
+            refresh,
+        } => match show::run(problem, en, refresh) {
+            Ok(_) => {}
+            Err(error) => {
+                err = Some(error);
+            }
+        },
+        Commands::debug {
+            file_name,
+            input,
+            all,
+            core,
+            rebuild,
+        } => match debug::run(file_name, input, all, core, rebuild) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
             }
         },
-        Commands::nodebug { file_name } => match nodebug::run(file_name) {
+        Commands::nodebug {
+            file_name,
+            input,
+            all,
+            rebuild,
+        } => match nodebug::run(file_name, input, all, rebuild) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
@@ -193,7 +413,9 @@ fn main() {
             problem_alphabet,
             auto,
             debug,
-        } => match service::contest::run(problem_alphabet, auto, debug) {
+            rebuild,
+            jobs,
+        } => match service::contest::run(problem_alphabet, auto, debug, rebuild, jobs) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
@@ -205,7 +427,17 @@ fn main() {
             problem_id,
             auto,
             debug,
-        } => match service::atcoder::run(contest_name, contest_id, problem_id, auto, debug) {
+            rebuild,
+            jobs,
+        } => match service::atcoder::run(
+            contest_name,
+            contest_id,
+            problem_id,
+            auto,
+            debug,
+            rebuild,
+            jobs,
+        ) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
@@ -216,7 +448,9 @@ fn main() {
             problem_id,
             auto,
             debug,
-        } => match service::codeforces::run(contest_id, problem_id, auto, debug) {
+            rebuild,
+            jobs,
+        } => match service::codeforces::run(contest_id, problem_id, auto, debug, rebuild, jobs) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
@@ -226,7 +460,9 @@ fn main() {
             problem_id,
             auto,
             debug,
-        } => match service::yukicoder::run(problem_id, auto, debug) {
+            rebuild,
+            jobs,
+        } => match service::yukicoder::run(problem_id, auto, debug, rebuild, jobs) {
             Ok(_) => {}
             Err(error) => {
                 err = Some(error);
@@ -234,10 +470,9 @@ fn main() {
         },
     }
 
-    match err {
-        Some(error) => {
-            println!("{} {:?}", Message::Error, error);
-        }
-        None => {}
+    if let Some(error) = err {
+        log::error(&format!("{:?}", error));
+        println!("{} {:?}", Message::Error, error);
+        process::exit(1);
     }
 }
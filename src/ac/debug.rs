@@ -6,13 +6,95 @@ use std::{
 };
 
 use ac_tools_rs::{
-    judge::{base_commands, subprocess},
+    compile,
+    header::{self, Profile},
+    judge::subprocess,
+    runner::Verdict,
     val::base_path,
     CustomError::*,
+    Message,
 };
+use colored::Colorize;
 use glob::glob;
 
-pub fn run(file_name: String) -> Result<(), Box<dyn Error>> {
+use crate::sample;
+
+pub fn run(
+    file_name: String,
+    input: Option<String>,
+    all: bool,
+    core: bool,
+    rebuild: bool,
+) -> Result<(), Box<dyn Error>> {
+    // コンテスト環境のディレクトリに移動する場合があるので、コンパイルより先に求める
+    let input_path = match &input {
+        Some(input) => Some(sample::input_path(&file_name, input)?),
+        None => None,
+    };
+    let cases = if all {
+        sample::cases(&file_name)?
+    } else {
+        Vec::new()
+    };
+
+    // コンパイル
+    {
+        // デバッグ情報(-g)はヘッダの設定で付与される
+        let variant = header::profile(Profile::Debug)?;
+        compile::build(&file_name, &variant, rebuild)?;
+    }
+
+    let file_name_path = format!("./{}", file_name);
+
+    if core {
+        return run_core(&file_name_path);
+    }
+
+    if all {
+        // 実行時エラーになったケースだけgdbで実行し直す
+        for outcome in sample::run_all(&file_name, &cases)? {
+            if outcome.verdict == Verdict::RE {
+                println!(
+                    "{} {}のバックトレースを表示します",
+                    Message::Info,
+                    outcome.case.name.bold()
+                );
+                gdb(&file_name_path, Some(&outcome.case.input))?;
+            }
+        }
+        return Ok(());
+    }
+
+    return gdb(&file_name_path, input_path.as_deref());
+}
+
+/// gdb上で実行し、クラッシュしたらバックトレースを表示する
+/// コアダンプの設定は不要
+fn gdb(file_name_path: &str, input: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let run_command = match input {
+        Some(input) => {
+            // runの引数はシェルで解釈されるので、パスをクォートする
+            let input = input.to_str().ok_or(InvalidUnicodeError)?;
+            format!("run < '{}'", input.replace('\'', "'\\''"))
+        }
+        None => String::from("run"),
+    };
+    let args = vec![
+        "-q",
+        "-batch",
+        "-ex",
+        &run_command,
+        "-ex",
+        "bt",
+        file_name_path,
+    ];
+    subprocess("gdb", args)?;
+
+    return Ok(());
+}
+
+/// 以前の方式: coredumpの出力先を`{BASE_PATH}/tmp`に変更している場合に使う
+fn run_core(file_name_path: &str) -> Result<(), Box<dyn Error>> {
     // 既存のcoredumpファイルを削除
     {
         let coredump_path_str = format!("{}/tmp", base_path()?);
@@ -22,23 +104,10 @@ pub fn run(file_name: String) -> Result<(), Box<dyn Error>> {
         }
     }
 
-    // コンパイル
-    {
-        let (cmd, mut args_cow) = base_commands(&file_name, "debug_all", true)?;
-        args_cow.push(Borrowed("-g"));
-
-        // Vec<Cow<str>>->Vec<&str>
-        let args = args_cow.iter().map(|i| i.as_ref()).collect();
-
-        subprocess(&cmd, args)?;
-    }
-
-    let file_name_path = format!("./{}", file_name);
-    
     // リソース制限を解除して実行
     // この方法だと"Segmentation fault"のメッセージが出ない
     {
-        let args = vec!["--core=unlimited", &file_name_path];
+        let args = vec!["--core=unlimited", file_name_path];
         subprocess("prlimit", args)?;
     }
 
@@ -56,7 +125,7 @@ pub fn run(file_name: String) -> Result<(), Box<dyn Error>> {
         match coredump {
             Some(coredump_path) => {
                 let args: Vec<&str> = vec![
-                    &file_name_path,
+                    file_name_path,
                     coredump_path.to_str().ok_or(InvalidUnicodeError)?,
                 ];
                 subprocess("gdb", args)?;
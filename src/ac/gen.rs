@@ -1,24 +1,38 @@
 use std::{
-    borrow::Cow::Borrowed,
+    borrow::Cow::{Borrowed, Owned},
     env,
     error::Error,
-    fs::{self, File},
-    io::Write,
-    path::Path,
+    fs,
+    io::{stdout, Write},
+    path::{Path, PathBuf},
     process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Duration,
 };
 
 use ac_tools_rs::{
+    judge::{ac_screen_converter, cf_converter, yuki_converter},
+    log, meta, runner,
+    template::{self, Context},
     val::{self, base_path},
     CustomError::*,
     Message, Warning,
 };
 use arboard::Clipboard;
-use chrono::{offset::LocalResult, Local, TimeZone};
+use chrono::{offset::LocalResult, DateTime, Local, TimeZone};
+use clap::Subcommand;
 use colored::Colorize;
 use dialoguer::{theme::ColorfulTheme, Input, Select};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use scraper::{Html, Selector};
 use serde::Deserialize;
 
+use crate::service::{
+    self,
+    contest::{ContestConfig, ContestProblem, Service},
+};
+
 #[derive(Deserialize, Debug)]
 struct YukiContest {
     #[serde(rename = "Name")]
@@ -43,10 +57,96 @@ struct VirtualContest {
 
 #[derive(Deserialize, Debug)]
 struct VirtualProblem {
+    /// 例: abc123_a, typical90_a
     id: String,
+    contest_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CfResponse<T> {
+    result: T,
+}
+
+#[derive(Deserialize, Debug)]
+struct CfContest {
+    id: u64,
+    #[serde(rename = "startTimeSeconds")]
+    start_time_seconds: Option<i64>,
 }
 
-pub fn run() -> Result<(), Box<dyn Error>> {
+#[derive(Deserialize, Debug)]
+struct CfStandings {
+    problems: Vec<CfProblem>,
+}
+
+#[derive(Deserialize, Debug)]
+struct CfProblem {
+    index: String,
+}
+
+/// コンテストの問題
+/// (ファイル名, 問題ID)
+/// 問題IDはAtCoderならabc123_aのようなもの、CodeforcesならD1のようなもの
+type Task = (String, String);
+
+/// サンプルをダウンロードする問題
+/// (ファイル名, テストケースのパス, 問題のURL)
+type Problem = (String, String, String);
+
+// 生成するコンテスト(省略した場合は対話形式で選択する)
+#[derive(Debug, Subcommand)]
+#[allow(non_camel_case_types)]
+pub enum Target {
+    /// 例: ac gen atcoder abc 350
+    atcoder {
+        contest_name: String,
+        contest_id: String,
+    },
+    /// 例: ac gen codeforces 1950
+    codeforces { contest_id: String },
+    /// 例: ac gen yukicoder 500
+    yukicoder { contest_id: String },
+    /// 例: ac gen virtual <ID>
+    #[command(name = "virtual")]
+    virtual_contest { id: String },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub wait: bool,
+    pub download: bool,
+    /// 確認をすべて省略する
+    pub yes: bool,
+}
+
+pub fn run(
+    target: Option<Target>,
+    opts: Options,
+    reset_problem: Option<String>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(problem_id) = reset_problem {
+        return reset(&problem_id);
+    }
+
+    let target = match target {
+        Some(target) => target,
+        None => return run_interactive(opts),
+    };
+
+    match target {
+        Target::atcoder {
+            contest_name,
+            contest_id,
+        } => atcoder(opts, Some((contest_name, contest_id)))?,
+        Target::codeforces { contest_id } => codeforces(opts, Some(contest_id))?,
+        Target::yukicoder { contest_id } => yukicoder(opts, Some(contest_id))?,
+        Target::virtual_contest { id } => virtual_contest(opts, Some(id))?,
+    }
+
+    return Ok(());
+}
+
+fn run_interactive(opts: Options) -> Result<(), Box<dyn Error>> {
     let choices = &["1:AtCoder", "2:Codeforces", "3:yukicoder", "4:バーチャル"];
     let choice = Select::with_theme(&ColorfulTheme::default())
         .with_prompt(format!(
@@ -58,75 +158,253 @@ pub fn run() -> Result<(), Box<dyn Error>> {
         .interact()?;
 
     if choice == 0 {
-        atcoder()?;
+        atcoder(opts, None)?;
     }
 
     if choice == 1 {
-        codeforces()?;
+        codeforces(opts, None)?;
     }
 
     if choice == 2 {
-        yukicoder()?;
+        yukicoder(opts, None)?;
     }
 
     if choice == 3 {
-        virtual_contest()?;
+        virtual_contest(opts, None)?;
     }
 
     return Ok(());
 }
 
+/// --yesが指定されていれば確認せずに続行する
+fn confirm(warning: Warning, yes: bool) -> Result<bool, Box<dyn Error>> {
+    if yes {
+        return Ok(true);
+    }
+    return warning.start();
+}
+
 /// pathの存在確認
-fn check_path(path: &Path) -> Result<bool, Box<dyn Error>> {
+fn check_path(path: &Path, yes: bool) -> Result<bool, Box<dyn Error>> {
     if !path.is_dir() {
         fs::create_dir_all(path)?;
         return Ok(true);
     } else {
         let (current_dir_name, parent_dir_name) = val::path_name(path)?;
 
-        return Ok(
-            Warning::Overwrite(format!("{}/{}", parent_dir_name, current_dir_name)).start()?,
+        return confirm(
+            Warning::Overwrite(format!("{}/{}", parent_dir_name, current_dir_name)),
+            yes,
         );
     }
 }
 
+/// num個の問題のファイル名(a, b, c, ...)
+fn problem_names(num: u8) -> Vec<String> {
+    return ('a'..(b'a' + num) as char).map(|i| i.to_string()).collect();
+}
+
 // 指定されたパスにコンテスト用のファイルを生成
-fn make_path(contest_path: &Path, num: u8) -> Result<(), Box<dyn Error>> {
-    let mut file_name_vec = vec![String::from("test")];
+// 解答が消えないように、テンプレートから変更されたファイルは変更しない
+fn make_path(contest_path: &Path, config: &ContestConfig) -> Result<(), Box<dyn Error>> {
+    let template = fs::read_to_string(template::path(&template_names(config))?)?;
+
+    let mut kept = Vec::new();
+    for context in contexts(config, None, template::uses_meta(&template))? {
+        let file_path = contest_path.join(format!("{}.cpp", context.problem));
+        // 開始前に生成したファイルなどは、問題の情報を埋め込んで生成し直す
+        if file_path.exists() && !is_pristine(&file_path, &template, &context)? {
+            kept.push(context.problem);
+            continue;
+        }
+        fs::write(file_path, template::render(&template, &context))?;
+    }
+
+    if !kept.is_empty() {
+        println!(
+            "{} 既に存在するファイルは変更しませんでした({})",
+            Message::Info,
+            kept.join(", ")
+        );
+    }
+
+    return Ok(());
+}
+
+/// 使用するテンプレートの候補(template.{name}.cpp)
+fn template_names(config: &ContestConfig) -> Vec<String> {
+    match config.service {
+        Service::Atcoder => {
+            if config
+                .problems
+                .iter()
+                .any(|problem| problem.contest.is_some())
+            {
+                return vec![String::from("virtual"), String::from("atcoder")];
+            }
+            // 例: abc350 -> abc
+            let contest_type: String = config
+                .contest_id
+                .chars()
+                .take_while(|c| c.is_ascii_alphabetic())
+                .collect();
+            return vec![contest_type, String::from("atcoder")];
+        }
+        Service::Codeforces => return vec![String::from("cf"), String::from("codeforces")],
+        Service::Yukicoder => return vec![String::from("yuki"), String::from("yukicoder")],
+    }
+}
 
-    for i in 'a'..('a' as u8 + num) as char {
-        file_name_vec.push(i.to_string());
+/// テンプレートに埋め込む値(test.cppと各問題)
+/// problem_idを指定した場合はその問題のみ
+/// with_metaがfalseなら問題の情報(問題名など)は取得しない
+fn contexts(
+    config: &ContestConfig,
+    problem_id: Option<&str>,
+    with_meta: bool,
+) -> Result<Vec<Context>, Box<dyn Error>> {
+    let contest = config.title.clone().unwrap_or(config.contest_id.clone());
+
+    let mut contexts = Vec::new();
+    if problem_id.is_none_or(|id| id == "test") {
+        contexts.push(Context {
+            problem: String::from("test"),
+            contest: contest.clone(),
+            ..Default::default()
+        });
     }
 
-    let template_path = val::template_path()?;
+    let problems: Vec<&ContestProblem> = config
+        .problems
+        .iter()
+        .filter(|problem| problem_id.is_none_or(|id| id == problem.id))
+        .collect();
+
+    // 問題ページの取得に時間がかかるので並列に取得する
+    // 開始前などで取得できない場合は問題の情報なしで生成する
+    let problem_contexts: Vec<Context> = problems
+        .par_iter()
+        .map(|problem| {
+            let context = Context {
+                problem: problem.id.clone(),
+                url: problem.url.clone(),
+                contest: contest.clone(),
+                ..Default::default()
+            };
+            if !with_meta {
+                return context;
+            }
+
+            let problem_meta =
+                config
+                    .paths(problem)
+                    .and_then(|(testcase_path_str, problem_url)| {
+                        meta::get(&testcase_path_str, &problem_url)
+                    });
+            match problem_meta {
+                Ok(problem_meta) => context.with_meta(&problem_meta),
+                Err(error) => {
+                    log::debug(&format!(
+                        "{} の問題情報を取得できませんでした: {:?}",
+                        problem.id, error
+                    ));
+                    context
+                }
+            }
+        })
+        .collect();
+    contexts.extend(problem_contexts);
+
+    return Ok(contexts);
+}
+
+/// テンプレートから変更されていないか
+fn is_pristine(
+    file_path: &Path,
+    template: &str,
+    context: &Context,
+) -> Result<bool, Box<dyn Error>> {
+    let content = fs::read_to_string(file_path)?;
+
+    return Ok(content == template
+        || template::is_rendered(template, context, &content)
+        || template::is_rendered(template, &context.without_meta(), &content));
+}
 
-    for i in file_name_vec {
-        fs::copy(&template_path, contest_path.join(format!("{}.cpp", i)))?;
+/// 問題のファイルをテンプレートに戻す
+/// テンプレートから変更されている場合は、上書きする前にアーカイブに退避する
+pub fn reset(problem_id: &str) -> Result<(), Box<dyn Error>> {
+    let config = service::contest::enter()?;
+    let template = fs::read_to_string(template::path(&template_names(&config))?)?;
+    let context = contexts(&config, Some(problem_id), template::uses_meta(&template))?
+        .pop()
+        .ok_or(ProblemNotFoundError(Owned(problem_id.to_string())))?;
+
+    let contest_path = env::current_dir()?;
+    let file_path = contest_path.join(format!("{}.cpp", problem_id));
+
+    if file_path.is_file() && !is_pristine(&file_path, &template, &context)? {
+        let archive_path = archive(&contest_path, &file_path)?;
+        println!(
+            "{} 変更前のファイルを退避しました: {}",
+            Message::Info,
+            archive_path.display()
+        );
     }
 
+    fs::write(&file_path, template::render(&template, &context))?;
+    println!(
+        "{} {}.cpp をテンプレートに戻しました",
+        Message::Success,
+        problem_id
+    );
+
     return Ok(());
 }
 
-fn atcoder() -> Result<(), Box<dyn Error>> {
+/// ファイルを`{コンテスト環境}/.archive/{日時}`にコピーする
+fn archive(contest_path: &Path, file_path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let archive_dir = contest_path
+        .join(".archive")
+        .join(Local::now().format("%Y%m%d-%H%M%S").to_string());
+    fs::create_dir_all(&archive_dir)?;
+
+    let archive_path = archive_dir.join(file_path.file_name().ok_or(InvalidUnicodeError)?);
+    fs::copy(file_path, &archive_path)?;
+
+    return Ok(archive_path);
+}
+
+fn atcoder(opts: Options, arg: Option<(String, String)>) -> Result<(), Box<dyn Error>> {
     let contest_name;
     let contest_id;
+    let contest_html;
 
     loop {
-        let tmp_contest_name: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!("{} コンテスト名", Message::Input))
-            .interact_text()?;
-
-        let tmp_contest_id: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!("{} コンテストID", Message::Input))
-            .interact_text()?;
+        let is_arg = arg.is_some();
+        let (tmp_contest_name, tmp_contest_id) = match arg.clone() {
+            Some(val) => val,
+            None => {
+                let tmp_contest_name: String = Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("{} コンテスト名", Message::Input))
+                    .interact_text()?;
+
+                let tmp_contest_id: String = Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("{} コンテストID", Message::Input))
+                    .interact_text()?;
+
+                (tmp_contest_name, tmp_contest_id)
+            }
+        };
 
         let contest_url = format!(
             "https://atcoder.jp/contests/{}{}",
             tmp_contest_name, tmp_contest_id
         );
 
-        match ureq::get(&contest_url).call() {
-            Ok(_) => {
+        match log::request("GET", &contest_url, ureq::get(&contest_url).call()) {
+            Ok(res) => {
+                contest_html = res.into_string()?;
                 contest_name = tmp_contest_name;
                 contest_id = tmp_contest_id;
                 println!(
@@ -138,6 +416,10 @@ fn atcoder() -> Result<(), Box<dyn Error>> {
                 break;
             }
             Err(error) => {
+                // 引数で指定された場合は入力し直せないので終了する
+                if is_arg {
+                    return Err(error);
+                }
                 println!("{} {}", Message::RequestError, error);
             }
         }
@@ -146,48 +428,365 @@ fn atcoder() -> Result<(), Box<dyn Error>> {
     let contest_path_str = format!("{}/contest/{}/{}", base_path()?, contest_name, contest_id);
 
     let contest_path = Path::new(&contest_path_str);
-    if !check_path(contest_path)? {
+    if !check_path(contest_path, opts.yes)? {
         return Ok(());
     }
 
-    make_path(contest_path, 8)?;
+    let contest_name_id = format!("{}{}", contest_name, contest_id);
+    let tasks = match atcoder_tasks(&contest_name_id) {
+        Ok(tasks) => tasks,
+        Err(error) => {
+            // 開始前は問題一覧が公開されていない
+            println!(
+                "{} 問題一覧の取得に失敗したので、a~hのファイルを生成します({})",
+                Message::Warning,
+                error
+            );
+            Vec::new()
+        }
+    };
+
+    // 例: <time class='fixtime-full'>2024-04-20 21:00:00+0900</time>
+    let start = atcoder_start(&contest_html);
+
+    let mut config = ContestConfig::new(Service::Atcoder, &contest_name_id);
+    config.start = start.as_ref().ok().map(|start| start.to_rfc3339());
+    config.problems = atcoder_problems(&contest_name_id, &tasks)?;
+    write_config(contest_path, &config)?;
     println!("{} コンテストの構成に成功しました", Message::Success);
 
     set_cd_clipboard(contest_path)?;
 
     check_login("https://atcoder.jp/")?;
 
+    if opts.wait {
+        wait_start(start?)?;
+
+        let tasks = retry(|| atcoder_tasks(&contest_name_id))?;
+        config.problems = atcoder_problems(&contest_name_id, &tasks)?;
+        write_config(contest_path, &config)?;
+
+        download_samples(&config_problems(&config)?);
+    } else if opts.download {
+        download_samples(&config_problems(&config)?);
+    }
+
     return Ok(());
 }
 
-fn codeforces() -> Result<(), Box<dyn Error>> {
-    let contest_id: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!("{} コンテスト番号", Message::Input))
-        .interact_text()?;
+fn atcoder_start(contest_html: &str) -> Result<DateTime<Local>, Box<dyn Error>> {
+    let doc = Html::parse_document(contest_html);
+    let selector = Selector::parse("time.fixtime-full")?;
+    let start_str = doc
+        .select(&selector)
+        .next()
+        .ok_or(HtmlError)?
+        .text()
+        .collect::<String>();
+
+    return Ok(
+        DateTime::parse_from_str(start_str.trim(), "%Y-%m-%d %H:%M:%S%z")?.with_timezone(&Local),
+    );
+}
+
+/// 問題一覧を取得できていなければa~hとみなす
+fn atcoder_problems(
+    contest_name_id: &str,
+    tasks: &[Task],
+) -> Result<Vec<ContestProblem>, Box<dyn Error>> {
+    let tasks = if tasks.is_empty() {
+        problem_names(8)
+            .into_iter()
+            .map(|file_name| {
+                let task_screen_name = format!("{}_{}", contest_name_id, file_name);
+                (file_name, task_screen_name)
+            })
+            .collect()
+    } else {
+        tasks.to_vec()
+    };
+
+    let mut problems = Vec::new();
+    for (file_name, task_screen_name) in tasks {
+        let (_, problem_url) = ac_screen_converter(contest_name_id, &task_screen_name)?;
+        problems.push(ContestProblem {
+            id: file_name,
+            task: task_screen_name,
+            contest: None,
+            url: problem_url,
+        });
+    }
+
+    return Ok(problems);
+}
+
+/// 問題一覧を取得できていなければa~hとみなす
+fn codeforces_problems(
+    contest_id: &str,
+    tasks: &[Task],
+) -> Result<Vec<ContestProblem>, Box<dyn Error>> {
+    let tasks = if tasks.is_empty() {
+        problem_names(8)
+            .into_iter()
+            .map(|file_name| (file_name.clone(), file_name.to_ascii_uppercase()))
+            .collect()
+    } else {
+        tasks.to_vec()
+    };
+
+    let mut problems = Vec::new();
+    for (file_name, index) in tasks {
+        let (_, problem_url) = cf_converter(contest_id, &index)?;
+        problems.push(ContestProblem {
+            id: file_name,
+            task: index,
+            contest: None,
+            url: problem_url,
+        });
+    }
+
+    return Ok(problems);
+}
+
+fn yuki_problems(contest_data: &YukiContest) -> Result<Vec<ContestProblem>, Box<dyn Error>> {
+    let mut problems = Vec::new();
+
+    for (file_name, id) in problem_names(contest_data.problem_id_list.len() as u8)
+        .into_iter()
+        .zip(&contest_data.problem_id_list)
+    {
+        let (_, problem_url) = yuki_converter(&id.to_string(), true)?;
+        problems.push(ContestProblem {
+            id: file_name,
+            task: id.to_string(),
+            contest: None,
+            url: problem_url,
+        });
+    }
+
+    return Ok(problems);
+}
+
+/// 問題一覧のページから問題を取得する
+fn atcoder_tasks(contest_name_id: &str) -> Result<Vec<Task>, Box<dyn Error>> {
+    let tasks_url = format!("https://atcoder.jp/contests/{}/tasks", contest_name_id);
+    let html = log::request("GET", &tasks_url, ureq::get(&tasks_url).call())?.into_string()?;
+    let doc = Html::parse_document(&html);
+
+    // 例: <td class="text-center no-break"><a href="/contests/abc123/tasks/abc123_a">A</a></td>
+    let selector = Selector::parse("table > tbody > tr > td:first-child > a")?;
+
+    let mut tasks = Vec::new();
+    for element in doc.select(&selector) {
+        let label = element.text().collect::<String>();
+        let task_screen_name = element
+            .value()
+            .attr("href")
+            .ok_or(HtmlError)?
+            .rsplit('/')
+            .next()
+            .ok_or(HtmlError)?;
+
+        tasks.push((
+            label.trim().to_ascii_lowercase(),
+            task_screen_name.to_string(),
+        ));
+    }
+
+    if tasks.is_empty() {
+        return Err(Box::new(HtmlError));
+    }
+
+    return Ok(tasks);
+}
+
+/// コンテストの順位表APIから問題を取得する
+fn codeforces_tasks(contest_id: &str) -> Result<Vec<Task>, Box<dyn Error>> {
+    let api_url = format!(
+        "https://codeforces.com/api/contest.standings?contestId={}&from=1&count=1",
+        contest_id
+    );
+    let standings: CfResponse<CfStandings> = match serde_json::from_value(get_request(&api_url)?) {
+        Ok(data) => data,
+        Err(_) => return Err(Box::new(InvalidJsonError)),
+    };
+
+    return Ok(standings
+        .result
+        .problems
+        .into_iter()
+        .map(|problem| (problem.index.to_ascii_lowercase(), problem.index))
+        .collect());
+}
+
+/// 開始直後は問題一覧が取得できないことがあるので、何回か再試行する
+fn retry(f: impl Fn() -> Result<Vec<Task>, Box<dyn Error>>) -> Result<Vec<Task>, Box<dyn Error>> {
+    let mut res = f();
+    for _ in 0..5 {
+        if res.is_ok() {
+            break;
+        }
+        thread::sleep(Duration::from_secs(3));
+        res = f();
+    }
+
+    return res;
+}
+
+/// .ac-contest.tomlを書き込み、問題のファイルを生成する
+fn write_config(contest_path: &Path, config: &ContestConfig) -> Result<(), Box<dyn Error>> {
+    let previous = ContestConfig::load(contest_path).ok();
+
+    config.save(contest_path)?;
+    make_path(contest_path, config)?;
+    if let Some(previous) = previous {
+        remove_stale(contest_path, &previous, config)?;
+    }
+
+    return Ok(());
+}
+
+/// 以前の問題一覧にあって新しい問題一覧にない問題のファイルを削除する
+/// 開始前に問題一覧を取得できずにa~hを生成した場合など
+/// テンプレートから変更されたファイルは削除せずに知らせる
+fn remove_stale(
+    contest_path: &Path,
+    previous: &ContestConfig,
+    config: &ContestConfig,
+) -> Result<(), Box<dyn Error>> {
+    let template = fs::read_to_string(template::path(&template_names(previous))?)?;
+
+    let mut removed = Vec::new();
+    let mut kept = Vec::new();
+    for context in contexts(previous, None, false)? {
+        if context.problem == "test"
+            || config
+                .problems
+                .iter()
+                .any(|problem| problem.id == context.problem)
+        {
+            continue;
+        }
+
+        let file_path = contest_path.join(format!("{}.cpp", context.problem));
+        if !file_path.is_file() {
+            continue;
+        }
+        if is_pristine(&file_path, &template, &context)? {
+            fs::remove_file(file_path)?;
+            removed.push(context.problem);
+        } else {
+            kept.push(context.problem);
+        }
+    }
+
+    if !removed.is_empty() {
+        println!(
+            "{} 問題一覧にないファイルを削除しました({})",
+            Message::Info,
+            removed.join(", ")
+        );
+    }
+    if !kept.is_empty() {
+        println!(
+            "{} 問題一覧にないファイルがありますが、変更されているので残しました({})",
+            Message::Warning,
+            kept.join(", ")
+        );
+    }
+
+    return Ok(());
+}
+
+/// サンプルをダウンロードする問題の一覧
+fn config_problems(config: &ContestConfig) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let mut problems = Vec::new();
+    for problem in &config.problems {
+        let (testcase_path_str, problem_url) = config.paths(problem)?;
+        problems.push((problem.id.clone(), testcase_path_str, problem_url));
+    }
+
+    return Ok(problems);
+}
+
+fn codeforces(opts: Options, arg: Option<String>) -> Result<(), Box<dyn Error>> {
+    let contest_id: String = match arg {
+        Some(val) => val,
+        None => Input::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("{} コンテスト番号", Message::Input))
+            .interact_text()?,
+    };
 
     let contest_path_str = format!("{}/contest/codeforces/{}", base_path()?, contest_id);
 
     let contest_path = Path::new(&contest_path_str);
-    if !check_path(contest_path)? {
+    if !check_path(contest_path, opts.yes)? {
         return Ok(());
     }
 
-    make_path(contest_path, 8)?;
+    let tasks = match codeforces_tasks(&contest_id) {
+        Ok(tasks) => tasks,
+        Err(error) => {
+            // 開始前は順位表が公開されていない
+            println!(
+                "{} 問題一覧の取得に失敗したので、a~hのファイルを生成します({})",
+                Message::Warning,
+                error
+            );
+            Vec::new()
+        }
+    };
+    let mut config = ContestConfig::new(Service::Codeforces, &contest_id);
+    config.problems = codeforces_problems(&contest_id, &tasks)?;
+    write_config(contest_path, &config)?;
     println!("{} コンテストの構成に成功しました", Message::Success);
 
     set_cd_clipboard(contest_path)?;
 
+    if opts.wait {
+        let api_url = "https://codeforces.com/api/contest.list";
+        let contests: CfResponse<Vec<CfContest>> =
+            match serde_json::from_value(get_request(api_url)?) {
+                Ok(data) => data,
+                Err(_) => return Err(Box::new(InvalidJsonError)),
+            };
+
+        let start_epoch_second = contests
+            .result
+            .iter()
+            .find(|contest| contest.id.to_string() == contest_id)
+            .ok_or(ContestNotFoundError)?
+            .start_time_seconds
+            .ok_or(InvalidJsonError)?;
+
+        let start = epoch_to_local(start_epoch_second)?;
+        config.start = Some(start.to_rfc3339());
+        wait_start(start)?;
+
+        let tasks = retry(|| codeforces_tasks(&contest_id))?;
+        config.problems = codeforces_problems(&contest_id, &tasks)?;
+        write_config(contest_path, &config)?;
+
+        download_samples(&config_problems(&config)?);
+    } else if opts.download {
+        download_samples(&config_problems(&config)?);
+    }
+
     return Ok(());
 }
 
-fn yukicoder() -> Result<(), Box<dyn Error>> {
+fn yukicoder(opts: Options, arg: Option<String>) -> Result<(), Box<dyn Error>> {
     let contest_id: String;
     let contest_data: YukiContest;
 
     loop {
-        let tmp_contest_id: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!("{} コンテストID", Message::Input))
-            .interact_text()?;
+        let is_arg = arg.is_some();
+        let tmp_contest_id: String = match arg.clone() {
+            Some(val) => val,
+            None => Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("{} コンテストID", Message::Input))
+                .interact_text()?,
+        };
 
         let api_url = format!("https://yukicoder.me/api/v1/contest/id/{}", tmp_contest_id);
 
@@ -202,6 +801,10 @@ fn yukicoder() -> Result<(), Box<dyn Error>> {
                 break;
             }
             Err(error) => {
+                // 引数で指定された場合は入力し直せないので終了する
+                if is_arg {
+                    return Err(error);
+                }
                 // エラーでもメッセージだけ表示して終了せずに続行
                 println!("{} {}", Message::RequestError, error);
             }
@@ -215,26 +818,22 @@ fn yukicoder() -> Result<(), Box<dyn Error>> {
         contest_data.date.replace("T", " ")
     );
 
-    if !Warning::Contest.start()? {
+    if !confirm(Warning::Contest, opts.yes)? {
         return Ok(());
     }
 
     let contest_path_str = format!("{}/contest/yukicoder/{}", base_path()?, contest_id);
 
     let contest_path = Path::new(&contest_path_str);
-    if !check_path(contest_path)? {
+    if !check_path(contest_path, opts.yes)? {
         return Ok(());
     }
 
-    let mut problem_file = File::create(contest_path.join("problems.txt"))?;
-
-    for id in &contest_data.problem_id_list {
-        problem_file.write_all((id.to_string() + "\n").as_bytes())?;
-    }
-
-    // 問題の個数
-    let num = contest_data.problem_id_list.len() as u8;
-    make_path(contest_path, num)?;
+    let mut config = ContestConfig::new(Service::Yukicoder, &contest_id);
+    config.title = Some(contest_data.name.clone());
+    config.start = Some(contest_data.date.clone());
+    config.problems = yuki_problems(&contest_data)?;
+    write_config(contest_path, &config)?;
 
     println!("{} コンテストの構成に成功しました", Message::Success);
 
@@ -242,33 +841,55 @@ fn yukicoder() -> Result<(), Box<dyn Error>> {
 
     check_login("https://yukicoder.me/")?;
 
+    if opts.wait {
+        let start = DateTime::parse_from_rfc3339(&contest_data.date)?.with_timezone(&Local);
+        wait_start(start)?;
+
+        // 開始前は問題一覧が確定していない可能性があるので取得し直す
+        let api_url = format!("https://yukicoder.me/api/v1/contest/id/{}", contest_id);
+        let contest_data: YukiContest = match serde_json::from_value(get_request(&api_url)?) {
+            Ok(data) => data,
+            Err(_) => return Err(Box::new(InvalidJsonError)),
+        };
+        config.problems = yuki_problems(&contest_data)?;
+        write_config(contest_path, &config)?;
+
+        download_samples(&config_problems(&config)?);
+    } else if opts.download {
+        download_samples(&config_problems(&config)?);
+    }
+
     return Ok(());
 }
 
-fn virtual_contest() -> Result<(), Box<dyn Error>> {
+fn virtual_contest(opts: Options, arg: Option<String>) -> Result<(), Box<dyn Error>> {
     // 本番用/コンテスト一覧の取得
     let api_url = "https://kenkoooo.com/atcoder/internal-api/contest/recent";
     let contests: Vec<RecentVirtualContest>;
 
     match get_request(api_url) {
-        Ok(res) => {
-            match serde_json::from_value(res) {
-                Ok(data) => contests = data,
-                Err(_) => return Err(Box::new(InvalidJsonError)),
-            }
-        }
+        Ok(res) => match serde_json::from_value(res) {
+            Ok(data) => contests = data,
+            Err(_) => return Err(Box::new(InvalidJsonError)),
+        },
         Err(error) => {
             println!("{} コンテスト一覧の取得に失敗しました", Message::Failed);
-            return Err(Box::new(error));
+            return Err(error);
         }
     };
 
-    let choices = &["1:環境変数", "2:IDから検索"];
-    let choice = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!("{} 検索方法を選択してください", Message::Question))
-        .items(choices)
-        .default(0)
-        .interact()?;
+    let choice = match arg {
+        // 引数で指定された場合はIDから検索する
+        Some(_) => 1,
+        None => {
+            let choices = &["1:環境変数", "2:IDから検索"];
+            Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("{} 検索方法を選択してください", Message::Question))
+                .items(choices)
+                .default(0)
+                .interact()?
+        }
+    };
 
     let mut contest_data: Option<RecentVirtualContest> = None;
 
@@ -305,9 +926,12 @@ fn virtual_contest() -> Result<(), Box<dyn Error>> {
             }
         }
     } else {
-        let contest_id: String = Input::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!("{} コンテストID:", Message::Input))
-            .interact_text()?;
+        let contest_id: String = match arg {
+            Some(val) => val,
+            None => Input::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("{} コンテストID:", Message::Input))
+                .interact_text()?,
+        };
 
         for contest in contests {
             if contest.id == contest_id {
@@ -324,27 +948,38 @@ fn virtual_contest() -> Result<(), Box<dyn Error>> {
         }
     };
 
-    let datetime = match Local.timestamp_opt(contest_data.start_epoch_second, 0) {
-        // 現地時間が複数ある場合は早いほうと遅いほうの二つが返ってくる可能性がある(LocalResult::Ambiguous)
-        // JSTで使う限りは関係なし
-        LocalResult::Single(val) => val,
-        LocalResult::Ambiguous(val, _) => val,
-        LocalResult::None => {
-            return Err("エポック秒の変換に失敗しました".into());
-        }
-    };
+    let datetime = epoch_to_local(contest_data.start_epoch_second)?;
 
     println!("{} コンテスト名: {}", Message::Info, contest_data.title);
     println!("{} 開始時刻: {}", Message::Info, datetime);
 
-    if !Warning::Contest.start()? {
+    if !confirm(Warning::Contest, opts.yes)? {
+        return Ok(());
+    }
+
+    // 複数のバーチャルコンテストを同時に扱えるようにIDごとにディレクトリを分ける
+    let contest_path_str = format!("{}/contest/virtual/{}", base_path()?, contest_data.id);
+    let contest_path = Path::new(&contest_path_str);
+    if !check_path(contest_path, opts.yes)? {
         return Ok(());
     }
 
+    // 問題情報は開始後に追記する
+    let mut config = ContestConfig::new(Service::Atcoder, &contest_data.id);
+    config.title = Some(contest_data.title);
+    config.start = Some(datetime.to_rfc3339());
+    config.save(contest_path)?;
+
+    // 問題情報は開始時刻を過ぎてから取得する
+    if opts.wait {
+        set_cd_clipboard(contest_path)?;
+        wait_start(datetime)?;
+    }
+
     // 本番用/問題情報の取得
     let api_url = format!(
         "https://kenkoooo.com/atcoder/internal-api/contest/get/{}",
-        contest_data.id
+        config.contest_id
     );
 
     let contest_data: VirtualContest;
@@ -355,64 +990,190 @@ fn virtual_contest() -> Result<(), Box<dyn Error>> {
         },
         Err(error) => {
             println!("{} 問題情報の取得に失敗しました", Message::Failed);
-            return Err(Box::new(error));
+            return Err(error);
         }
     };
 
-    let contest_path_str = format!("{}/contest/virtual", base_path()?);
-    let contest_path = Path::new(&contest_path_str);
-    if !check_path(contest_path)? {
-        return Ok(());
-    }
+    let file_names = problem_names(contest_data.problems.len() as u8);
+    for (file_name, problem) in file_names.into_iter().zip(contest_data.problems) {
+        // APIにcontest_idが含まれていない場合は問題IDから推測する(例: abc123_a -> abc123)
+        let contest_id = match problem.contest_id {
+            Some(contest_id) => contest_id,
+            None => problem
+                .id
+                .rsplit_once('_')
+                .ok_or(UnsupportedContestError)?
+                .0
+                .to_string(),
+        };
 
-    let mut problem_file = File::create(contest_path.join("virtual_problems.txt"))?;
+        let (_, problem_url) = ac_screen_converter(&contest_id, &problem.id)?;
 
-    for problem in &contest_data.problems {
-        // example: abc123_a
-        let problem_data: Vec<&str> = problem.id.split("_").collect();
+        config.problems.push(ContestProblem {
+            id: file_name,
+            task: problem.id,
+            contest: Some(contest_id),
+            url: problem_url,
+        });
+    }
+    write_config(contest_path, &config)?;
 
-        if problem_data.len() != 2 {
-            return Err(Box::new(UnsupportedContestError));
-        }
+    println!("{} コンテストの構成に成功しました", Message::Success);
 
-        // 一連の操作で配列外参照を検知するようにはなっているが、"_"以前が3文字以上あればどんな文字列でも通してしまう
-        // おそらく"3文字のコンテスト名+コンテスト番号"以外で使うことはない
-        // 文字数+文字種で正しいフォーマットか検出することもできるが、結局検出にも限界があるし自分で使うだけならそこまでやる意味もなさそう
+    if !opts.wait {
+        set_cd_clipboard(contest_path)?;
+    }
 
-        let contest_name_id: &str = problem_data.get(0).ok_or(IndexError)?;
-        let problem_id: &str = problem_data.get(1).ok_or(IndexError)?;
+    if opts.wait || opts.download {
+        download_samples(&config_problems(&config)?);
+    }
 
-        // contest name
-        if let Some(val) = contest_name_id.get(..3) {
-            problem_file.write_all(val.as_bytes())?;
-            problem_file.write_all(b"\n")?;
-        } else {
-            return Err(Box::new(UnsupportedContestError));
-        }
-        // contest id
-        if let Some(val) = contest_name_id.get(3..) {
-            problem_file.write_all(val.as_bytes())?;
-            problem_file.write_all(b"\n")?;
-        } else {
-            return Err(Box::new(UnsupportedContestError));
+    return Ok(());
+}
+
+fn epoch_to_local(epoch_second: i64) -> Result<DateTime<Local>, Box<dyn Error>> {
+    match Local.timestamp_opt(epoch_second, 0) {
+        // 現地時間が複数ある場合は早いほうと遅いほうの二つが返ってくる可能性がある(LocalResult::Ambiguous)
+        // JSTで使う限りは関係なし
+        LocalResult::Single(val) => return Ok(val),
+        LocalResult::Ambiguous(val, _) => return Ok(val),
+        LocalResult::None => {
+            return Err("エポック秒の変換に失敗しました".into());
         }
-        // problem id
-        problem_file.write_all(problem_id.as_bytes())?;
-        problem_file.write_all(b"\n")?;
     }
+}
 
-    let num = contest_data.problems.len() as u8;
-    make_path(contest_path, num)?;
+/// 開始時刻までカウントダウンを表示して待機
+fn wait_start(start: DateTime<Local>) -> Result<(), Box<dyn Error>> {
+    loop {
+        let remaining = (start - Local::now()).num_seconds();
+        if remaining <= 0 {
+            break;
+        }
 
-    println!("{} コンテストの構成に成功しました", Message::Success);
+        print!(
+            "\r{} 開始まで {:02}:{:02}:{:02} ",
+            Message::Info,
+            remaining / 3600,
+            remaining % 3600 / 60,
+            remaining % 60
+        );
+        stdout().flush()?;
 
-    set_cd_clipboard(contest_path)?;
+        thread::sleep(Duration::from_secs(1));
+    }
+    println!();
+    println!("{} コンテストが開始されました", Message::Success);
 
     return Ok(());
 }
 
-fn get_request(url: &str) -> Result<serde_json::Value, ureq::Error> {
-    let response = ureq::get(&url).call()?;
+/// サンプルを並列にダウンロード
+/// 問題が存在しない場合やサンプルがない問題もあるので、失敗しても他の問題のダウンロードは続行する
+fn download_samples(problems: &[Problem]) {
+    println!(
+        "{} {}問のサンプルをダウンロードしています...",
+        Message::Info,
+        problems.len()
+    );
+
+    let total = problems.len();
+    let finished = AtomicUsize::new(0);
+    let succeeded = AtomicUsize::new(0);
+
+    problems
+        .par_iter()
+        .for_each(|(file_name, testcase_path_str, problem_url)| {
+            let testcase_path = Path::new(testcase_path_str);
+            let label = file_name.bold();
+
+            // 既にダウンロード済み
+            // 追加したテストケースしかない場合はダウンロードする
+            let res = if runner::has_samples(testcase_path) {
+                Ok(None)
+            } else {
+                println!("{} {}: ダウンロードを開始します", Message::Info, label);
+
+                // 出力が混ざらないように標準出力はキャプチャする
+                Command::new("oj")
+                    .args(["d", problem_url, "-d", testcase_path_str])
+                    .output()
+                    .map(Some)
+            };
+
+            let progress = format!(
+                "({}/{})",
+                finished.fetch_add(1, Ordering::SeqCst) + 1,
+                total
+            );
+            match res {
+                Ok(None) => {
+                    succeeded.fetch_add(1, Ordering::SeqCst);
+                    println!(
+                        "{} {}: ダウンロード済みです {}",
+                        Message::Info,
+                        label,
+                        progress
+                    );
+                }
+                Ok(Some(output)) => {
+                    let cnt = runner::sample_count(testcase_path);
+                    if output.status.success() && cnt > 0 {
+                        succeeded.fetch_add(1, Ordering::SeqCst);
+                        if let Err(error) = meta::get(testcase_path_str, problem_url) {
+                            log::warn(&format!(
+                                "{} の情報の取得に失敗しました: {}",
+                                problem_url, error
+                            ));
+                        }
+                        println!(
+                            "{} {}: {}個のサンプルをダウンロードしました {}",
+                            Message::Success,
+                            label,
+                            cnt,
+                            progress
+                        );
+                    } else {
+                        log::warn(&format!(
+                            "{} のサンプルを取得できませんでした\n{}",
+                            problem_url,
+                            String::from_utf8_lossy(&output.stdout)
+                        ));
+
+                        // サンプルがない問題はテスト時にojのエラーメッセージが表示されるので、ここでは警告だけ
+                        if output.status.success() {
+                            println!(
+                                "{} {}: サンプルが存在しませんでした {}",
+                                Message::Warning,
+                                label,
+                                progress
+                            );
+                        } else {
+                            println!(
+                                "{} {}: ダウンロードに失敗しました {}",
+                                Message::Failed,
+                                label,
+                                progress
+                            );
+                        }
+                    }
+                }
+                Err(error) => {
+                    println!("{} {}: {} {}", Message::Failed, label, error, progress);
+                }
+            }
+        });
+
+    println!(
+        "{} {}/{}問のサンプルを取得しました",
+        Message::Success,
+        succeeded.load(Ordering::SeqCst),
+        total
+    );
+}
+
+fn get_request(url: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+    let response = log::request("GET", url, ureq::get(url).call())?;
 
     let res = response.into_json()?;
     return Ok(res);
@@ -458,13 +1219,13 @@ mod tests {
     #[should_panic]
     fn test_check_path() {
         let path = Path::new("/");
-        check_path(path).unwrap();
+        check_path(path, false).unwrap();
     }
 
     #[test]
     #[should_panic]
     fn test_make_path() {
         let path = Path::new("/test");
-        check_path(path).unwrap();
+        check_path(path, false).unwrap();
     }
 }
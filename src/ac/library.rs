@@ -0,0 +1,334 @@
+use std::{
+    borrow::Cow::Owned,
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ac_tools_rs::{val::base_path, CustomError::*, Message};
+use clap::Subcommand;
+use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, Select};
+
+use crate::service;
+
+/// 検索結果として表示する最大数
+const MAX_RESULTS: usize = 10;
+
+#[derive(Debug, Subcommand)]
+#[allow(non_camel_case_types)]
+pub enum Command {
+    /// ライブラリの一覧をタグとともに表示する
+    list {},
+
+    /// ファイル名とタグからあいまい検索する
+    search { query: String },
+
+    /// 検索したライブラリを問題のファイルに追加する
+    add {
+        /// 追加先の問題ID(例: a)
+        problem: String,
+
+        query: String,
+
+        /// #includeではなくコードを直接貼り付ける
+        #[arg(short = 'p', long = "paste", action)]
+        paste: bool,
+    },
+}
+
+/// ライブラリのファイル
+#[derive(Debug)]
+struct Entry {
+    path: PathBuf,
+    /// AC_BASE_PATHからの相対パス(例: library/ds/segtree.hpp)
+    include_path: String,
+    /// `// @tag segtree range-query`のように書かれたタグ
+    tags: Vec<String>,
+}
+
+pub fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    let entries = index()?;
+
+    match command {
+        Command::list {} => {
+            for entry in &entries {
+                print_entry(entry);
+            }
+        }
+        Command::search { query } => {
+            let found = search(&entries, &query);
+            if found.is_empty() {
+                println!("{} 一致するライブラリがありませんでした", Message::Failed);
+            }
+            for entry in found {
+                print_entry(entry);
+            }
+        }
+        Command::add {
+            problem,
+            query,
+            paste,
+        } => {
+            let found = search(&entries, &query);
+            let entry = match found.len() {
+                0 => {
+                    println!("{} 一致するライブラリがありませんでした", Message::Failed);
+                    return Ok(());
+                }
+                1 => found[0],
+                _ => {
+                    let items: Vec<&str> = found
+                        .iter()
+                        .map(|entry| entry.include_path.as_str())
+                        .collect();
+                    let choice = Select::with_theme(&ColorfulTheme::default())
+                        .with_prompt(format!(
+                            "{} 追加するライブラリを選択してください",
+                            Message::Question
+                        ))
+                        .items(&items)
+                        .default(0)
+                        .interact()?;
+                    found[choice]
+                }
+            };
+
+            add(&problem, entry, paste)?;
+        }
+    }
+
+    return Ok(());
+}
+
+fn print_entry(entry: &Entry) {
+    println!(
+        "{} {}",
+        entry.include_path.bold(),
+        entry.tags.join(" ").bright_black()
+    );
+}
+
+/// `{AC_BASE_PATH}/library`以下のファイルを集める
+/// プリコンパイル用のヘッダ(library/header)は除く
+fn index() -> Result<Vec<Entry>, Box<dyn Error>> {
+    let base_path_str = base_path()?;
+    let library_path = Path::new(&base_path_str).join("library");
+    if !library_path.is_dir() {
+        return Err(Box::new(DirNotfoundError(Owned(
+            library_path.display().to_string(),
+        ))));
+    }
+
+    let mut paths = Vec::new();
+    collect(&library_path, &library_path.join("header"), &mut paths)?;
+    paths.sort();
+
+    let mut entries = Vec::new();
+    for path in paths {
+        let include_path = path
+            .strip_prefix(&base_path_str)?
+            .to_str()
+            .ok_or(InvalidUnicodeError)?
+            .to_string();
+        let tags = parse_tags(&fs::read_to_string(&path)?);
+        entries.push(Entry {
+            path,
+            include_path,
+            tags,
+        });
+    }
+
+    return Ok(entries);
+}
+
+fn collect(dir: &Path, exclude: &Path, paths: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        if path.is_dir() {
+            if path != exclude {
+                collect(&path, exclude, paths)?;
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "hpp" || ext == "h" || ext == "cpp")
+        {
+            paths.push(path);
+        }
+    }
+
+    return Ok(());
+}
+
+/// コメント中の`@tag`の後に書かれた単語(空白またはカンマ区切り)
+fn parse_tags(code: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    for line in code.lines() {
+        let line = line.trim_start();
+        if !(line.starts_with("//") || line.starts_with('*') || line.starts_with("/*")) {
+            continue;
+        }
+        if let Some((_, rest)) = line.split_once("@tag") {
+            tags.extend(
+                rest.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|tag| !tag.is_empty() && *tag != "*/")
+                    .map(|tag| tag.to_string()),
+            );
+        }
+    }
+
+    return tags;
+}
+
+/// スコアの高い順に最大MAX_RESULTS件
+fn search<'a>(entries: &'a [Entry], query: &str) -> Vec<&'a Entry> {
+    let mut scored: Vec<(i64, &Entry)> = entries
+        .iter()
+        .filter_map(|entry| {
+            // タグの完全一致を優先する
+            let tag_bonus = if entry.tags.iter().any(|tag| tag.eq_ignore_ascii_case(query)) {
+                100
+            } else {
+                0
+            };
+            let text = format!("{} {}", entry.include_path, entry.tags.join(" "));
+            fuzzy_score(query, &text).map(|score| (score + tag_bonus, entry))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.include_path.cmp(&b.1.include_path)));
+
+    return scored
+        .into_iter()
+        .take(MAX_RESULTS)
+        .map(|(_, entry)| entry)
+        .collect();
+}
+
+/// queryの文字がtextに順番通りに含まれていればスコアを返す
+/// 連続して一致する文字や単語の先頭での一致ほどスコアが高い
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev: Option<usize> = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (pos..text.len()).find(|&i| text[i] == c)?;
+
+        score += 1;
+        if prev.is_some_and(|prev| prev + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        prev = Some(found);
+        pos = found + 1;
+    }
+
+    // 短いパスほど目的のものである可能性が高い
+    return Some(score * 10 - text.len() as i64 / 10);
+}
+
+/// 問題のファイルにライブラリを追加する
+fn add(problem: &str, entry: &Entry, paste: bool) -> Result<(), Box<dyn Error>> {
+    // コンテスト環境内ならそのディレクトリの問題のファイル
+    let dir = match service::contest::find_dir() {
+        Ok(dir) => dir,
+        Err(_) => env::current_dir()?,
+    };
+    let file_path = dir.join(format!("{}.cpp", problem));
+    let code = fs::read_to_string(&file_path)
+        .map_err(|_| Box::new(FileNotfoundError(Owned(format!("{}.cpp", problem)))))?;
+
+    let include = format!("#include \"{}\"", entry.include_path);
+    let marker = format!("// ---- {} ----", entry.include_path);
+    if code.contains(&include) || code.contains(&marker) {
+        println!(
+            "{} {} は既に追加されています",
+            Message::Info,
+            entry.include_path
+        );
+        return Ok(());
+    }
+
+    let snippet = if paste {
+        let library_code = fs::read_to_string(&entry.path)?;
+        let body: Vec<&str> = library_code
+            .lines()
+            .filter(|line| line.trim() != "#pragma once")
+            .collect();
+        if body
+            .iter()
+            .any(|line| line.trim_start().starts_with("#include \""))
+        {
+            println!(
+                "{} 貼り付けたコードに含まれるライブラリの#includeは展開されません",
+                Message::Warning
+            );
+        }
+        format!("{}\n{}\n// ---- end ----", marker, body.join("\n").trim())
+    } else {
+        include
+    };
+
+    fs::write(&file_path, insert(&code, &snippet))?;
+    println!(
+        "{} {} を {}.cpp に追加しました",
+        Message::Success,
+        entry.include_path,
+        problem
+    );
+
+    return Ok(());
+}
+
+/// 最後の#includeの次の行に挿入する(#includeがなければ先頭)
+fn insert(code: &str, snippet: &str) -> String {
+    let lines: Vec<&str> = code.lines().collect();
+    let index = lines
+        .iter()
+        .rposition(|line| line.trim_start().starts_with("#include"))
+        .map_or(0, |i| i + 1);
+
+    let mut res: Vec<&str> = Vec::new();
+    res.extend_from_slice(&lines[..index]);
+    res.push(snippet);
+    res.extend_from_slice(&lines[index..]);
+
+    return res.join("\n") + "\n";
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::library::{fuzzy_score, insert, parse_tags};
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("seg", "library/ds/segtree.hpp").is_some());
+        assert!(fuzzy_score("sgt", "library/ds/segtree.hpp").is_some());
+        assert!(fuzzy_score("xyz", "library/ds/segtree.hpp").is_none());
+        assert!(
+            fuzzy_score("seg", "library/ds/segtree.hpp")
+                > fuzzy_score("seg", "library/string/suffix_array_graph.hpp")
+        );
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        let code = "#pragma once\n// @tag segtree, range-query\nint a; // @tag ignored\n";
+        assert_eq!(parse_tags(code), vec!["segtree", "range-query"]);
+    }
+
+    #[test]
+    fn test_insert() {
+        let code = "#include <bits/stdc++.h>\nusing namespace std;\n";
+        assert_eq!(
+            insert(code, "#include \"library/a.hpp\""),
+            "#include <bits/stdc++.h>\n#include \"library/a.hpp\"\nusing namespace std;\n"
+        );
+        assert_eq!(insert("int main() {}\n", "X"), "X\nint main() {}\n");
+    }
+}
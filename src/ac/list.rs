@@ -0,0 +1,79 @@
+use std::error::Error;
+
+use ac_tools_rs::{
+    meta::{self, Meta},
+    thread_error, Message,
+};
+use colored::{ColoredString, Colorize};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::service;
+
+pub fn run() -> Result<(), Box<dyn Error>> {
+    let config = service::contest::enter()?;
+
+    // 問題ごとに通信が必要な場合があるので並列に取得する
+    let metas: Vec<Result<Meta, String>> = config
+        .problems
+        .par_iter()
+        .map(|problem| {
+            let (testcase_path_str, problem_url) = config.paths(problem).map_err(thread_error)?;
+            meta::get_with_difficulty(&testcase_path_str, &problem_url).map_err(thread_error)
+        })
+        .collect();
+
+    let problems = config.problems.iter().map(|problem| &problem.id);
+    for (problem, problem_meta) in problems.zip(metas) {
+        match problem_meta {
+            Ok(problem_meta) => {
+                let time_limit = problem_meta
+                    .time_limit_ms
+                    .map_or(String::from("-"), |ms| format!("{}s", ms as f64 / 1000.0));
+                let memory_limit = problem_meta
+                    .memory_limit_mb
+                    .map_or(String::from("-"), |mb| format!("{}MB", mb));
+                let score = problem_meta
+                    .score
+                    .map_or(String::from("-"), |score| score.to_string());
+                let difficulty = problem_meta
+                    .difficulty
+                    .map_or(format!("{:>5}", "-").normal(), colored_difficulty);
+
+                println!(
+                    "{} {:>6} {:>7} {:>5} {}  {}",
+                    format!("{:<4}", problem).bold(),
+                    time_limit,
+                    memory_limit,
+                    score,
+                    difficulty,
+                    problem_meta.title.unwrap_or_default()
+                );
+            }
+            Err(error) => {
+                println!(
+                    "{} {} {}",
+                    format!("{:<4}", problem).bold(),
+                    Message::Failed,
+                    error
+                );
+            }
+        }
+    }
+
+    return Ok(());
+}
+
+/// AtCoderのレーティングの色で表示する
+fn colored_difficulty(difficulty: i64) -> ColoredString {
+    let text = format!("{:>5}", difficulty);
+    return match difficulty {
+        ..400 => text.bright_black(),
+        400..800 => text.truecolor(128, 64, 0),
+        800..1200 => text.green(),
+        1200..1600 => text.cyan(),
+        1600..2000 => text.blue(),
+        2000..2400 => text.yellow(),
+        2400..2800 => text.truecolor(255, 128, 0),
+        _ => text.red(),
+    };
+}
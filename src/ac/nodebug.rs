@@ -1,17 +1,53 @@
-use std::error::Error;
+use std::{error::Error, fs::File, process::Command};
 
-use ac_tools_rs::judge::{base_commands, subprocess};
+use ac_tools_rs::{
+    compile,
+    header::{self, Profile},
+    judge::subprocess,
+};
+
+use crate::sample;
+
+pub fn run(
+    file_name: String,
+    input: Option<String>,
+    all: bool,
+    rebuild: bool,
+) -> Result<(), Box<dyn Error>> {
+    // コンテスト環境のディレクトリに移動する場合があるので、コンパイルより先に求める
+    let input_path = match &input {
+        Some(input) => Some(sample::input_path(&file_name, input)?),
+        None => None,
+    };
+    let cases = if all {
+        sample::cases(&file_name)?
+    } else {
+        Vec::new()
+    };
 
-pub fn run(file_name: String) -> Result<(), Box<dyn Error>> {
     // コンパイル
     {
-        let (cmd, args_cow) = base_commands(&file_name, "nodebug_all", true)?;
-        let args = args_cow.iter().map(|i| i.as_ref()).collect();
-        subprocess(&cmd, args)?;
+        let variant = header::profile(Profile::NoDebug)?;
+        compile::build(&file_name, &variant, rebuild)?;
+    }
+
+    if all {
+        sample::run_all(&file_name, &cases)?;
+        return Ok(());
     }
 
     // 実行
-    subprocess(&format!("./{}", file_name), Vec::new())?;
+    let file_name_path = format!("./{}", file_name);
+    match input_path {
+        Some(input_path) => {
+            Command::new(&file_name_path)
+                .stdin(File::open(input_path)?)
+                .status()?;
+        }
+        None => {
+            subprocess(&file_name_path, Vec::new())?;
+        }
+    }
 
     return Ok(());
 }
@@ -1,98 +1,83 @@
-use std::{
-    borrow::Cow::Borrowed,
-    error::Error,
-    process::{Command, Stdio},
-};
+use std::error::Error;
 
 use ac_tools_rs::{
-    judge::{header_commands, option_commands},
-    CustomError::*,
-    Message, Warning,
+    compile::CompileError,
+    header::{self, Variant},
+    thread_error, Message, Warning,
 };
 use colored::Colorize;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+pub fn run(force: bool, yes: bool) -> Result<(), Box<dyn Error>> {
+    // ヘッダや引数が変わっていないものは生成し直さない
+    let mut stale = Vec::new();
+    for variant in header::variants()? {
+        if force || header::is_stale(&variant)? {
+            stale.push(variant);
+        }
+    }
 
-pub fn run() -> Result<(), Box<dyn Error>> {
-    if !Warning::Precompile.start()? {
+    if stale.is_empty() {
+        println!(
+            "{} 全てのプリコンパイル済みヘッダは最新です",
+            Message::Success
+        );
         return Ok(());
     }
 
-    let headers = vec![
-        "all",
-        "debug_all",
-        "nodebug_all",
-        "oj_all",
-        "oj_nodebug_all",
-    ];
-
-    let mut args_vec = Vec::new();
-
-    // all.hpp
-    let (cmd, mut args) = header_commands("all", true)?;
-    args.append(&mut option_commands()?);
-    args_vec.push(args);
-
-    // debug_all.hpp
-    let (_, mut args) = header_commands("debug_all", true)?;
-    args.push(Borrowed("-g"));
-    args_vec.push(args);
+    let names: Vec<&str> = stale.iter().map(|variant| variant.name.as_str()).collect();
+    println!("{} 対象: {}", Message::Info, names.join(", "));
 
-    // nodebug_all.hpp
-    let (_, args) = header_commands("nodebug_all", true)?;
-    args_vec.push(args);
-
-    // oj_all.hpp
-    let (_, mut args) = header_commands("oj_all", false)?;
-    args.append(&mut option_commands()?);
-    args_vec.push(args);
-
-    // oj_nodebug_all.hpp
-    let (_, args) = header_commands("oj_nodebug_all", false)?;
-    args_vec.push(args);
+    if !yes && !Warning::Precompile.start()? {
+        return Ok(());
+    }
 
     // スレッドに入れていく
-    match (0..args_vec.len()).into_par_iter().try_for_each(|i| {
-        // Vec<Cow<str>->Vec<&str>
-        let args = args_vec
-            .get(i)
-            .ok_or(IndexError)?
-            .iter()
-            .map(|i| i.as_ref())
-            .collect();
+    // 一つが失敗しても残りのヘッダのコンパイルは続ける
+    let results: Vec<Result<(), String>> = stale.par_iter().map(build).collect();
+
+    // 出力が混ざらないように、失敗したヘッダごとにまとめて表示する
+    let mut failed = Vec::new();
+    for (variant, result) in stale.iter().zip(results) {
+        if let Err(output) = result {
+            println!(
+                "{} {}.hppのコンパイルに失敗しました",
+                Message::Failed,
+                variant.name.bold()
+            );
+            eprintln!("{}", output.trim_end());
+            failed.push(variant.name.as_str());
+        }
+    }
 
-        subprocess(&cmd, args, headers.get(i).ok_or(IndexError)?)
-    }) {
-        Ok(_) => {}
-        // エラーの型が違うのでStringにしてから変換する必要がある
-        Err(error) => return Err(error.to_string().into()),
+    if !failed.is_empty() {
+        return Err(format!(
+            "プリコンパイルに失敗したヘッダがあります({})",
+            failed.join(", ")
+        )
+        .into());
     }
 
     return Ok(());
 }
 
-// 非同期処理は戻り値を変えないといけないのでjudge.rsとは別に必要
-fn subprocess(
-    command: &str,
-    args: Vec<&str>,
-    header_name: &str,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
+/// 並列に実行するので、失敗した場合はコンパイラの出力を文字列で返す
+fn build(variant: &Variant) -> Result<(), String> {
     println!(
         "{} {}.hppをコンパイルしています...",
         Message::Info,
-        header_name.bold()
+        variant.name.bold()
     );
 
-    Command::new(command)
-        .args(args)
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .output()?;
+    header::build(variant).map_err(|error| match error.downcast_ref::<CompileError>() {
+        Some(error) => error.output.clone(),
+        None => thread_error(error),
+    })?;
 
     println!(
         "{} {}.hppのコンパイルに成功しました✅",
         Message::Success,
-        header_name.bold()
+        variant.name.bold()
     );
 
     return Ok(());
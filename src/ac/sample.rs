@@ -0,0 +1,95 @@
+use std::{
+    borrow::Cow::Owned,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use ac_tools_rs::{
+    runner::{self, Case, Options, Outcome},
+    CustomError::*,
+    Message,
+};
+use colored::Colorize;
+
+use crate::service;
+
+/// `--input`の値から入力ファイルを求める
+/// 数字ならコンテスト環境内の問題のsample-N.in、それ以外はファイルのパス
+/// 問題から求めた場合、カレントディレクトリはコンテスト環境のディレクトリになる
+pub fn input_path(problem_id: &str, input: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = match input.parse::<u32>() {
+        Ok(n) => {
+            let (testcase_path_str, _) = service::contest::resolve(problem_id)?;
+            Path::new(&testcase_path_str).join(format!("sample-{}.in", n))
+        }
+        Err(_) => PathBuf::from(input),
+    };
+
+    if !path.is_file() {
+        return Err(Box::new(FileNotfoundError(Owned(
+            path.display().to_string(),
+        ))));
+    }
+
+    return Ok(path);
+}
+
+/// コンテスト環境内の問題のテストケース
+/// カレントディレクトリはコンテスト環境のディレクトリになる
+pub fn cases(problem_id: &str) -> Result<Vec<Case>, Box<dyn Error>> {
+    let (testcase_path_str, _) = service::contest::resolve(problem_id)?;
+    let cases = runner::cases(Path::new(&testcase_path_str))?;
+    if cases.is_empty() {
+        return Err(Box::new(FileNotfoundError(Owned(format!(
+            "{}/*.in",
+            testcase_path_str
+        )))));
+    }
+
+    return Ok(cases);
+}
+
+/// 全てのケースを順に実行し、出力を期待される出力と並べて表示する
+/// デバッグ出力を見るためのものなので、実行時間制限などは設けない
+pub fn run_all(file_name: &str, cases: &[Case]) -> Result<Vec<Outcome>, Box<dyn Error>> {
+    let options = Options {
+        error: match cases.iter().find_map(|case| case.expected.as_ref()) {
+            Some(expected) => runner::float_error(&fs::read_to_string(expected)?),
+            None => None,
+        },
+        ..Default::default()
+    };
+
+    let mut res = Vec::new();
+    for case in cases {
+        let outcome = runner::run_case(
+            Path::new(&format!("./{}", file_name)),
+            &format!("{}.cpp", file_name),
+            case,
+            &options,
+        )?;
+
+        println!(
+            "{} {} {} {}ms",
+            if outcome.passed() {
+                Message::Success
+            } else {
+                Message::Failed
+            },
+            outcome.case.name.bold(),
+            outcome.verdict,
+            outcome.elapsed.as_millis()
+        );
+        runner::print_comparison(&outcome)?;
+        if !outcome.stderr.is_empty() {
+            runner::print_section("標準エラー出力", &outcome.stderr);
+        }
+        println!();
+
+        res.push(outcome);
+    }
+    runner::summary(&res);
+
+    return Ok(res);
+}
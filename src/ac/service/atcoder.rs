@@ -8,10 +8,20 @@ pub fn run(
     problem_id: String,
     auto: bool,
     debug: bool,
+    rebuild: bool,
+    jobs: Option<usize>,
 ) -> Result<(), Box<dyn Error>> {
     let (testcase_path_str, problem_url) = ac_converter(&contest_name, &contest_id, &problem_id)?;
 
-    judge::run(testcase_path_str, problem_url, "main", auto, debug)?;
+    judge::run(
+        testcase_path_str,
+        problem_url,
+        "main",
+        auto,
+        debug,
+        rebuild,
+        jobs,
+    )?;
 
     return Ok(());
 }
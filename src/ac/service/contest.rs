@@ -1,128 +1,293 @@
 use std::{
-    borrow::Cow::Borrowed,
+    borrow::Cow::{Borrowed, Owned},
     env,
     error::Error,
-    fs::File,
-    io::{BufRead, BufReader},
+    fs,
+    path::{Path, PathBuf},
 };
 
 use ac_tools_rs::{
-    judge::{self, ac_converter, cf_converter, yuki_converter},
+    judge::{self, ac_screen_converter, cf_converter, yuki_converter},
     val,
     CustomError::*,
 };
+use serde::{Deserialize, Serialize};
 
-pub fn run(problem_alphabet: String, auto: bool, debug: bool) -> Result<(), Box<dyn Error>> {
-    let current_dir = env::current_dir()?;
-    let (current_dir_name, parent_dir_name) = val::path_name(&current_dir)?;
-
-    if parent_dir_name == "codeforces" {
-        codeforces(problem_alphabet, auto, debug)?;
-    } else if parent_dir_name == "yukicoder" {
-        yukicoder(problem_alphabet, auto, debug)?;
-    } else if current_dir_name == "virtual" {
-        virtual_contest(problem_alphabet, auto, debug)?;
-    } else {
-        atcoder(problem_alphabet, auto, debug)?;
-    }
+/// コンテスト環境の情報を記録するファイル
+/// このファイルがあるディレクトリをコンテスト環境として扱う
+pub const CONFIG_FILE: &str = ".ac-contest.toml";
 
-    return Ok(());
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Service {
+    Atcoder,
+    Codeforces,
+    Yukicoder,
 }
 
-// コンテストごとの各関数の引数の命名規則
-// atcoder/codeforces -> problem_alphabet=contest_idとなるので、引数の時点でproblem_idでok
-// yukicoder/virtual -> ファイルから取得して変換が必要なのでproblem_alphabetのままにする
+/// .ac-contest.tomlの内容
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ContestConfig {
+    pub service: Service,
+    /// 例: abc123(AtCoder), 1950(Codeforces), 500(yukicoder), バーチャルコンテストのID
+    pub contest_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 開始時刻(RFC3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    pub language: String,
+    #[serde(default)]
+    pub problems: Vec<ContestProblem>,
+}
 
-fn atcoder(problem_id: String, auto: bool, debug: bool) -> Result<(), Box<dyn Error>> {
-    // ディレクトリ名取得
-    let current_dir = env::current_dir()?;
-    let (contest_id, contest_name) = val::path_name(&current_dir)?;
+/// コンテスト環境内の問題
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ContestProblem {
+    /// ファイル名(例: a, ex, d1)
+    pub id: String,
+    /// 例: abc123_a(AtCoder), A(Codeforces), 10000(yukicoder)
+    pub task: String,
+    /// 問題が属するコンテスト(バーチャルコンテストのみ)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contest: Option<String>,
+    pub url: String,
+}
 
-    let (testcase_path_str, problem_url) = ac_converter(&contest_name, &contest_id, &problem_id)?;
-    judge::run(testcase_path_str, problem_url, &problem_id, auto, debug)?;
+impl ContestConfig {
+    pub fn new(service: Service, contest_id: &str) -> ContestConfig {
+        return ContestConfig {
+            service,
+            contest_id: contest_id.to_string(),
+            title: None,
+            start: None,
+            language: String::from("cpp"),
+            problems: Vec::new(),
+        };
+    }
 
-    return Ok(());
+    pub fn load(contest_path: &Path) -> Result<ContestConfig, Box<dyn Error>> {
+        let text = fs::read_to_string(contest_path.join(CONFIG_FILE))
+            .map_err(|_| Box::new(FileNotfoundError(Borrowed(CONFIG_FILE))))?;
+        return Ok(toml::from_str(&text)?);
+    }
+
+    pub fn save(&self, contest_path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(contest_path.join(CONFIG_FILE), toml::to_string(self)?)?;
+        return Ok(());
+    }
+
+    /// 問題の(テストケースのパス, 問題のURL)
+    pub fn paths(&self, problem: &ContestProblem) -> Result<(String, String), Box<dyn Error>> {
+        let (testcase_path_str, _) = match self.service {
+            Service::Atcoder => ac_screen_converter(
+                problem.contest.as_deref().unwrap_or(&self.contest_id),
+                &problem.task,
+            )?,
+            Service::Codeforces => cf_converter(&self.contest_id, &problem.task)?,
+            Service::Yukicoder => yuki_converter(&problem.task, true)?,
+        };
+
+        return Ok((testcase_path_str, problem.url.clone()));
+    }
 }
 
-fn codeforces(problem_id: String, auto: bool, debug: bool) -> Result<(), Box<dyn Error>> {
+/// カレントディレクトリから親をたどって、.ac-contest.tomlがあるディレクトリを探す
+pub fn find_dir() -> Result<PathBuf, Box<dyn Error>> {
     let current_dir = env::current_dir()?;
-    let (contest_id, _) = val::path_name(&current_dir)?;
+    for dir in current_dir.ancestors() {
+        if dir.join(CONFIG_FILE).is_file() {
+            return Ok(dir.to_path_buf());
+        }
+    }
+
+    return Err(Box::new(FileNotfoundError(Borrowed(CONFIG_FILE))));
+}
 
-    let (testcase_path_str, problem_url) = cf_converter(&contest_id, &problem_id)?;
-    judge::run(testcase_path_str, problem_url, &problem_id, auto, debug)?;
+/// コンテスト環境のディレクトリに移動して、その情報を読み込む
+/// .ac-contest.tomlがない場合は、以前の方式でカレントディレクトリから推測する
+pub fn enter() -> Result<ContestConfig, Box<dyn Error>> {
+    let contest_path = match find_dir() {
+        Ok(contest_path) => contest_path,
+        Err(_) => return legacy_config(&env::current_dir()?),
+    };
+    env::set_current_dir(&contest_path)?;
 
-    return Ok(());
+    return ContestConfig::load(&contest_path);
 }
 
-fn yukicoder(problem_alphabet: String, auto: bool, debug: bool) -> Result<(), Box<dyn Error>> {
-    // ファイル読み込み
-    let current_dir = env::current_dir()?;
-    let problem_file = File::open(current_dir.join("problems.txt"))
-        .or_else(|_| Err(Box::new(FileNotfoundError(Borrowed("problems.txt")))))?;
-    let mut problems = Vec::new();
-    for i in BufReader::new(problem_file).lines() {
-        problems.push(i?);
+/// .ac-contest.tomlがないディレクトリの情報を推測する
+/// 以前のバージョンで生成したコンテスト環境では、ディレクトリ名とtasks.txtなどから求めていた
+fn legacy_config(contest_path: &Path) -> Result<ContestConfig, Box<dyn Error>> {
+    let (current_dir_name, parent_dir_name) = val::path_name(contest_path)?;
+
+    // contest/virtual/virtual_problems.txtに、問題ごとにコンテスト名、コンテスト番号、問題IDを一行ずつ書いていた
+    // 例: abc\n123\na\n
+    let virtual_path = contest_path.join("virtual_problems.txt");
+    if virtual_path.is_file() {
+        let text = fs::read_to_string(virtual_path)?;
+        let lines: Vec<&str> = text.lines().collect();
+        let problems = lines.chunks_exact(3);
+        if !problems.remainder().is_empty() {
+            return Err(Box::new(IndexError));
+        }
+
+        let mut config = ContestConfig::new(Service::Atcoder, &current_dir_name);
+        for (index, problem) in problems.enumerate() {
+            let contest_id = format!("{}{}", problem[0], problem[1]);
+            let task = format!("{}_{}", contest_id, problem[2]);
+            let (_, url) = ac_screen_converter(&contest_id, &task)?;
+            config.problems.push(ContestProblem {
+                id: legacy_problem_id(index),
+                task,
+                contest: Some(contest_id),
+                url,
+            });
+        }
+
+        return Ok(config);
     }
 
-    // アルファベットから何個目の問題かを調べる
-    let base_char = 'a'.to_ascii_lowercase() as usize;
-    let index = problem_alphabet
-        .chars()
-        .nth(0)
-        .ok_or(IndexError)?
-        .to_ascii_lowercase() as usize
-        - base_char;
-    let problem_id = problems.get(index).ok_or(IndexError)?;
-
-    let (testcase_path_str, problem_url) = yuki_converter(problem_id, true)?;
-    judge::run(
-        testcase_path_str,
-        problem_url,
-        &problem_alphabet,
-        auto,
-        debug,
-    )?;
+    if parent_dir_name == "yukicoder" {
+        let text = fs::read_to_string(contest_path.join("problems.txt"))
+            .map_err(|_| Box::new(FileNotfoundError(Borrowed("problems.txt"))))?;
 
-    return Ok(());
+        let mut config = ContestConfig::new(Service::Yukicoder, &current_dir_name);
+        for (index, task) in text.lines().enumerate() {
+            let (_, url) = yuki_converter(task, true)?;
+            config.problems.push(ContestProblem {
+                id: legacy_problem_id(index),
+                task: task.to_string(),
+                contest: None,
+                url,
+            });
+        }
+
+        return Ok(config);
+    }
+
+    let (service, contest_id) = if parent_dir_name == "codeforces" {
+        (Service::Codeforces, current_dir_name)
+    } else {
+        // 例: contest/abc/123
+        (
+            Service::Atcoder,
+            format!("{}{}", parent_dir_name, current_dir_name),
+        )
+    };
+    let mut config = ContestConfig::new(service, &contest_id);
+    for (id, task) in legacy_tasks(contest_path, service, &contest_id)? {
+        let (_, url) = if service == Service::Codeforces {
+            cf_converter(&contest_id, &task)?
+        } else {
+            ac_screen_converter(&contest_id, &task)?
+        };
+        config.problems.push(ContestProblem {
+            id,
+            task,
+            contest: None,
+            url,
+        });
+    }
+
+    return Ok(config);
+}
+
+/// tasks.txtの(ファイル名, 問題ID)
+/// tasks.txtがない(問題一覧を取得できなかった)場合は、ディレクトリ内の.cppファイルを問題とする
+fn legacy_tasks(
+    contest_path: &Path,
+    service: Service,
+    contest_id: &str,
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    if let Ok(text) = fs::read_to_string(contest_path.join("tasks.txt")) {
+        return Ok(text
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(id, task)| (id.to_string(), task.to_string()))
+            .collect());
+    }
+
+    let mut res = Vec::new();
+    for file in fs::read_dir(contest_path)? {
+        let path = file?.path();
+        if path.extension().is_none_or(|ext| ext != "cpp") {
+            continue;
+        }
+        if let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) {
+            let task = match service {
+                Service::Atcoder => format!("{}_{}", contest_id, id),
+                _ => id.to_string(),
+            };
+            res.push((id.to_string(), task));
+        }
+    }
+    res.sort();
+
+    return Ok(res);
+}
+
+/// 何番目の問題か(a, b, c, ...)
+fn legacy_problem_id(index: usize) -> String {
+    return ((b'a' + index as u8) as char).to_string();
 }
 
-fn virtual_contest(
-    problem_alphabet: String,
+pub fn run(
+    problem_id: String,
     auto: bool,
     debug: bool,
+    rebuild: bool,
+    jobs: Option<usize>,
 ) -> Result<(), Box<dyn Error>> {
-    // ファイル読み込み
-    let current_dir = env::current_dir()?;
-    let problem_file = File::open(current_dir.join("virtual_problems.txt")).or_else(|_| {
-        Err(Box::new(FileNotfoundError(Borrowed(
-            "virtual_problems.txt",
-        ))))
-    })?;
-    let mut problems: Vec<String> = Vec::new();
-    for i in BufReader::new(problem_file).lines() {
-        problems.push(i?);
-    }
-
-    // アルファベットから何個目の問題かを調べる
-    let base_char = 'a'.to_ascii_lowercase() as usize;
-    let index = problem_alphabet
-        .chars()
-        .nth(0)
-        .ok_or(IndexError)?
-        .to_ascii_lowercase() as usize
-        - base_char;
-    let contest_name = problems.get(index * 3).ok_or(IndexError)?;
-    let contest_id = problems.get(index * 3 + 1).ok_or(IndexError)?;
-    let problem_id = problems.get(index * 3 + 2).ok_or(IndexError)?;
-
-    let (testcase_path_str, problem_url) = ac_converter(contest_name, contest_id, problem_id)?;
+    let (testcase_path_str, problem_url) = resolve(&problem_id)?;
     judge::run(
         testcase_path_str,
         problem_url,
-        &problem_alphabet,
+        &problem_id,
         auto,
         debug,
+        rebuild,
+        jobs,
     )?;
 
     return Ok(());
 }
+
+/// コンテスト環境内の問題から(テストケースのパス, 問題のURL)を求める
+/// 実行後のカレントディレクトリはコンテスト環境のディレクトリになる
+pub fn resolve(problem_id: &str) -> Result<(String, String), Box<dyn Error>> {
+    let config = enter()?;
+
+    let problem = config
+        .problems
+        .iter()
+        .find(|problem| problem.id == problem_id)
+        .ok_or(ProblemNotFoundError(Owned(problem_id.to_string())))?;
+
+    return config.paths(problem);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::service::contest::{ContestConfig, ContestProblem, Service};
+
+    #[test]
+    fn test_config() {
+        let mut config = ContestConfig::new(Service::Atcoder, "abc123");
+        config.problems.push(ContestProblem {
+            id: String::from("a"),
+            task: String::from("abc123_a"),
+            contest: None,
+            url: String::from("https://atcoder.jp/contests/abc123/tasks/abc123_a"),
+        });
+
+        let text = toml::to_string(&config).unwrap();
+        assert!(text.contains("service = \"atcoder\""));
+        assert!(!text.contains("title"));
+
+        let config: ContestConfig = toml::from_str(&text).unwrap();
+        assert_eq!(config.service, Service::Atcoder);
+        assert_eq!(config.problems[0].task, "abc123_a");
+        assert_eq!(config.problems[0].contest, None);
+    }
+}
@@ -2,10 +2,24 @@ use std::error::Error;
 
 use ac_tools_rs::judge::{self, yuki_converter};
 
-pub fn run(problem_id: String, auto: bool, debug: bool) -> Result<(), Box<dyn Error>> {
+pub fn run(
+    problem_id: String,
+    auto: bool,
+    debug: bool,
+    rebuild: bool,
+    jobs: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
     let (testcase_path_str, problem_url) = yuki_converter(&problem_id, false)?;
 
-    judge::run(testcase_path_str, problem_url, "main", auto, debug)?;
+    judge::run(
+        testcase_path_str,
+        problem_url,
+        "main",
+        auto,
+        debug,
+        rebuild,
+        jobs,
+    )?;
 
     return Ok(());
 }
@@ -0,0 +1,364 @@
+use std::{error::Error, fs, path::Path, sync::LazyLock};
+
+use ac_tools_rs::{log, CustomError::*, Message};
+use colored::Colorize;
+use regex::{Captures, Regex};
+use scraper::{node::Node, ElementRef, Html, Selector};
+
+use crate::service;
+
+/// simplify_tex中で\{ \}を表す文字(Unicodeの私用領域)
+const ESCAPED_BRACES: [&str; 2] = ["\u{E000}", "\u{E001}"];
+
+/// Codeforces($$$...$$$)やyukicoder($...$)の数式
+static MATH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$\$\$(.+?)\$\$\$|\$([^$]+?)\$").unwrap());
+static FRAC: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\[dt]?frac\{([^{}]*)\}\{([^{}]*)\}").unwrap());
+static SQRT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\\sqrt\{([^{}]*)\}").unwrap());
+static FONT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\\(?:mathrm|text|textrm|mathbf|mathit|mathtt|operatorname|texttt)\{([^{}]*)\}")
+        .unwrap()
+});
+static TEX_COMMAND: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\\([a-zA-Z]+|[, ;!])").unwrap());
+static SCRIPT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"([_^])\{([^{}]*)\}").unwrap());
+
+pub fn run(problem: String, en: bool, refresh: bool) -> Result<(), Box<dyn Error>> {
+    let lang = if en { "en" } else { "ja" };
+
+    // URLで指定された場合は問題ごとのディレクトリがないのでキャッシュしない
+    let (cache_path, problem_url) = if problem.starts_with("http") {
+        (None, problem)
+    } else {
+        let (testcase_path_str, problem_url) = service::contest::resolve(&problem)?;
+        (
+            Some(format!("{}.statement-{}.txt", testcase_path_str, lang)),
+            problem_url,
+        )
+    };
+
+    let statement = match &cache_path {
+        Some(path) if !refresh && Path::new(path).is_file() => fs::read_to_string(path)?,
+        _ => {
+            let html =
+                log::request("GET", &problem_url, ureq::get(&problem_url).call())?.into_string()?;
+            let statement = render(&html, &problem_url, lang)?;
+
+            if let Some(path) = &cache_path {
+                if let Some(parent) = Path::new(path).parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, &statement)?;
+            }
+
+            statement
+        }
+    };
+
+    println!("{} {}\n", Message::Info, problem_url);
+    for line in statement.lines() {
+        match line.strip_prefix("## ") {
+            Some(heading) => println!("{}", heading.bold().underline()),
+            None => println!("{}", line),
+        }
+    }
+
+    return Ok(());
+}
+
+/// 問題ページのHTMLから問題文を取り出してテキストにする
+fn render(html: &str, problem_url: &str, lang: &str) -> Result<String, Box<dyn Error>> {
+    let doc = Html::parse_document(html);
+    let mut res = String::new();
+
+    if problem_url.contains("atcoder.jp") {
+        // タイトルと制限は問題文の外にある
+        let title_selector = Selector::parse("span.h2")?;
+        if let Some(title) = doc.select(&title_selector).next() {
+            // 解説ボタンのテキストが含まれるので最初のテキストだけ使う
+            let title = title.text().next().unwrap_or_default().trim();
+            res += &format!("## {}\n", title);
+        }
+
+        let limit_selector = Selector::parse("#main-container p")?;
+        if let Some(limit) = doc.select(&limit_selector).find(|element| {
+            let text = element.text().collect::<String>();
+            text.contains("Time Limit") || text.contains("実行時間制限")
+        }) {
+            res += limit.text().collect::<String>().trim();
+            res += "\n";
+        }
+
+        let statement_selector = Selector::parse(if lang == "en" {
+            "#task-statement span.lang-en"
+        } else {
+            "#task-statement span.lang-ja"
+        })?;
+        let fallback_selector = Selector::parse("#task-statement")?;
+        let statement = doc
+            .select(&statement_selector)
+            .next()
+            .or_else(|| doc.select(&fallback_selector).next())
+            .ok_or(HtmlError)?;
+        render_element(statement, &mut res, false);
+    } else if problem_url.contains("codeforces.com") {
+        let selector = Selector::parse(".problem-statement")?;
+        let statement = doc.select(&selector).next().ok_or(HtmlError)?;
+        render_element(statement, &mut res, false);
+    } else {
+        let selector = Selector::parse("#content")?;
+        let statement = doc.select(&selector).next().ok_or(HtmlError)?;
+        render_element(statement, &mut res, false);
+    }
+
+    return Ok(cleanup(&res));
+}
+
+fn render_element(element: ElementRef, res: &mut String, in_pre: bool) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                if in_pre {
+                    res.push_str(text);
+                } else {
+                    // HTML上の改行やインデントは1つの空白にまとめる
+                    let collapsed = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+                    if collapsed.is_empty() {
+                        continue;
+                    }
+                    // 要素の境界の空白は残す(例: "<var>N</var> 個")
+                    if text.starts_with(char::is_whitespace) && !res.ends_with([' ', '\n']) {
+                        res.push(' ');
+                    }
+                    res.push_str(&simplify_math(&collapsed));
+                    if text.ends_with(char::is_whitespace) {
+                        res.push(' ');
+                    }
+                }
+            }
+            Node::Element(_) => {
+                let Some(child) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                render_child(child, res, in_pre);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn render_child(element: ElementRef, res: &mut String, in_pre: bool) {
+    let value = element.value();
+    let has_class = |class: &str| value.classes().any(|i| i == class);
+
+    // コピーボタンなどの問題文と関係ない要素
+    if has_class("btn-copy") || has_class("div-btn-copy") || has_class("btn") {
+        return;
+    }
+
+    match value.name() {
+        "script" | "style" | "button" | "form" | "nav" | "noscript" => {}
+        "var" => {
+            let tex = element.text().collect::<String>();
+            res.push_str(&simplify_tex(&tex));
+        }
+        "pre" => {
+            res.push_str("\n\n");
+            let mut pre = String::new();
+            render_element(element, &mut pre, true);
+            for line in pre.trim_matches('\n').lines() {
+                res.push_str("    ");
+                res.push_str(line);
+                res.push('\n');
+            }
+            res.push('\n');
+        }
+        "h1" | "h2" | "h3" | "h4" | "h5" => push_heading(element, res),
+        "br" => res.push('\n'),
+        "li" => {
+            res.push_str("\n- ");
+            render_element(element, res, in_pre);
+            res.push('\n');
+        }
+        "img" => res.push_str("[画像]"),
+        "td" | "th" => {
+            render_element(element, res, in_pre);
+            res.push_str("  ");
+        }
+        "p" | "div" | "section" | "ul" | "ol" | "table" | "tr" | "blockquote" => {
+            // Codeforcesのセクション名(Input, Outputなど)は見出しとして扱う
+            if !in_pre && (has_class("section-title") || has_class("title")) {
+                push_heading(element, res);
+                return;
+            }
+            // Codeforcesの制限(time limit per test 2 seconds)は1行にまとめる
+            if has_class("property-title") {
+                res.push_str(element.text().collect::<String>().trim());
+                res.push_str(": ");
+                return;
+            }
+
+            if !res.ends_with('\n') {
+                res.push('\n');
+            }
+            render_element(element, res, in_pre);
+            if !res.ends_with('\n') {
+                res.push('\n');
+            }
+        }
+        _ => render_element(element, res, in_pre),
+    }
+}
+
+fn push_heading(element: ElementRef, res: &mut String) {
+    // 見出し内のコピーボタンなどを除くため、テキストを直接取り出さずに描画する
+    let mut heading = String::new();
+    render_element(element, &mut heading, false);
+
+    res.push_str("\n\n## ");
+    res.push_str(&heading.split_whitespace().collect::<Vec<&str>>().join(" "));
+    res.push_str("\n\n");
+}
+
+/// 連続した空行や行末の空白を取り除く
+fn cleanup(text: &str) -> String {
+    let mut res = String::new();
+    let mut blank = 0;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank += 1;
+            if blank > 1 {
+                continue;
+            }
+        } else {
+            blank = 0;
+        }
+        res.push_str(line);
+        res.push('\n');
+    }
+
+    return res.trim().to_string() + "\n";
+}
+
+/// Codeforces($$$...$$$)やyukicoder($...$)のテキスト中の数式を簡略化する
+fn simplify_math(text: &str) -> String {
+    return MATH
+        .replace_all(text, |caps: &Captures| {
+            let tex = caps
+                .get(1)
+                .or_else(|| caps.get(2))
+                .map_or("", |i| i.as_str());
+            simplify_tex(tex)
+        })
+        .into_owned();
+}
+
+/// TeXの数式を端末で読みやすいテキストにする
+/// 完全な変換はできないので、よく使われるコマンドのみ記号に置き換える
+pub fn simplify_tex(tex: &str) -> String {
+    // 集合などの\{ \}は、グループ化のための括弧と区別するために一時的に別の文字にしておく
+    let mut res = tex
+        .replace(r"\{", ESCAPED_BRACES[0])
+        .replace(r"\}", ESCAPED_BRACES[1]);
+
+    // \frac{a}{b} -> (a)/(b), \sqrt{x} -> √(x)
+    // 入れ子に対応するため、変化がなくなるまで繰り返す
+    loop {
+        let next = FRAC.replace_all(&res, "($1)/($2)").into_owned();
+        let next = SQRT.replace_all(&next, "√($1)").into_owned();
+        let next = FONT.replace_all(&next, "$1").into_owned();
+        if next == res {
+            break;
+        }
+        res = next;
+    }
+
+    res = TEX_COMMAND
+        .replace_all(&res, |caps: &Captures| {
+            let name = &caps[1];
+            let symbol = match name {
+                "le" | "leq" | "leqq" => "≤",
+                "ge" | "geq" | "geqq" => "≥",
+                "lt" => "<",
+                "gt" => ">",
+                "ne" | "neq" => "≠",
+                "times" => "×",
+                "cdot" => "·",
+                "div" => "÷",
+                "pm" => "±",
+                "ldots" | "cdots" | "dots" | "vdots" => "…",
+                "sum" => "Σ",
+                "prod" => "Π",
+                "infty" => "∞",
+                "in" => "∈",
+                "notin" => "∉",
+                "subset" => "⊂",
+                "subseteq" => "⊆",
+                "cup" => "∪",
+                "cap" => "∩",
+                "land" | "wedge" => "∧",
+                "lor" | "vee" => "∨",
+                "oplus" => "⊕",
+                "to" | "rightarrow" => "→",
+                "leftarrow" => "←",
+                "lfloor" => "⌊",
+                "rfloor" => "⌋",
+                "lceil" => "⌈",
+                "rceil" => "⌉",
+                "mid" => "|",
+                "bmod" | "mod" => " mod ",
+                "lbrace" => ESCAPED_BRACES[0],
+                "rbrace" => ESCAPED_BRACES[1],
+                "," | " " | ";" | "quad" | "qquad" => " ",
+                "!" | "left" | "right" | "displaystyle" | "limits" => "",
+                // 未知のコマンドは名前をそのまま残す
+                _ => name,
+            };
+            symbol.to_string()
+        })
+        .into_owned();
+
+    // ^{10} -> ^10, _{i} -> _i, _{i+1} -> _(i+1)
+    // 英数字以外を含む場合は、どこまでが添字かわかるように括弧を残す
+    res = SCRIPT
+        .replace_all(&res, |caps: &Captures| {
+            let body = caps[2].trim();
+            if body.chars().all(|c| c.is_alphanumeric()) {
+                format!("{}{}", &caps[1], body)
+            } else {
+                format!("{}({})", &caps[1], body)
+            }
+        })
+        .into_owned();
+
+    // 残った(グループ化のための)括弧を取り除く
+    res.retain(|c| c != '{' && c != '}');
+    res = res
+        .replace(ESCAPED_BRACES[0], "{")
+        .replace(ESCAPED_BRACES[1], "}");
+
+    // 1\leq N -> 1≤ N のように空白が偏るので、記号の前後の空白を整える
+    return res.split_whitespace().collect::<Vec<&str>>().join(" ");
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::show::simplify_tex;
+
+    #[test]
+    fn test_simplify_tex() {
+        assert_eq!(
+            simplify_tex(r"1 \leq N \leq 2 \times 10^5"),
+            "1 ≤ N ≤ 2 × 10^5"
+        );
+        assert_eq!(simplify_tex(r"A_{i} \neq A_{i+1}"), "A_i ≠ A_(i+1)");
+        assert_eq!(simplify_tex(r"2^{N-1} + 10^{18}"), "2^(N-1) + 10^18");
+        assert_eq!(simplify_tex(r"S = \{ 1, 2 \}"), "S = { 1, 2 }");
+        assert_eq!(simplify_tex(r"\left\{ x_{i} \right\}"), "{ x_i }");
+        assert_eq!(simplify_tex(r"\frac{N}{2}"), "(N)/(2)");
+        assert_eq!(simplify_tex(r"\mathrm{Yes}"), "Yes");
+        assert_eq!(simplify_tex(r"(A_1,\ldots,A_N)"), "(A_1,…,A_N)");
+    }
+}
@@ -13,18 +13,16 @@ pub fn run(oj: bool) -> Result<(), Box<dyn Error>> {
     let problem_file = fs::File::open(format!("{}/library/url_latest.txt", base_path()?))?;
     let mut problem_file_buf = BufReader::new(problem_file).lines();
 
-    let problem_url;
-    let file_name;
     // get index 0
-    match problem_file_buf.next() {
-        Some(val) => problem_url = val?,
+    let problem_url = match problem_file_buf.next() {
+        Some(val) => val?,
         None => return Err(Box::new(IndexError)),
-    }
+    };
     // get index 1
-    match problem_file_buf.next() {
-        Some(val) => file_name = val?,
+    let file_name = match problem_file_buf.next() {
+        Some(val) => val?,
         None => return Err(Box::new(IndexError)),
-    }
+    };
 
     if problem_url.contains("codeforces.com") {
         println!(
@@ -35,15 +33,11 @@ pub fn run(oj: bool) -> Result<(), Box<dyn Error>> {
     }
 
     if !oj && problem_url.contains("atcoder.jp") && env::var("AC_USE_OJ").is_err() {
-        let problem_data_vec: Vec<&str> = problem_url.split("/").collect();
-        let mut problem_data = problem_data_vec
-            .get(problem_data_vec.len() - 1)
-            .ok_or(IndexError)?
-            .split("_");
+        let (contest_id, task_screen_name) = submission::atcoder_task(&problem_url)?;
 
         submission::run(
-            problem_data.next().ok_or(IndexError)?,
-            problem_data.next().ok_or(IndexError)?,
+            contest_id,
+            task_screen_name,
             env::current_dir()?.join(format!("{}.cpp", &file_name)),
             0,
         )?;
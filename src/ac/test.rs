@@ -8,6 +8,8 @@ pub fn run(
     custom: bool,
     auto: bool,
     debug: bool,
+    rebuild: bool,
+    jobs: Option<usize>,
 ) -> Result<(), Box<dyn Error>> {
     let problem_url;
     let contest_name_id;
@@ -36,12 +38,18 @@ pub fn run(
     }
     // url
     else {
-        problem_url = arg1;
-
-        testcase_path_str = format!("{}/test/url", base_path()?);
+        (testcase_path_str, problem_url) = judge::url_converter(&arg1)?;
     }
 
-    judge::run(testcase_path_str, problem_url, "main", auto, debug)?;
+    judge::run(
+        testcase_path_str,
+        problem_url,
+        "main",
+        auto,
+        debug,
+        rebuild,
+        jobs,
+    )?;
 
     return Ok(());
 }
@@ -1,115 +1,242 @@
 use std::{
-    borrow::Cow::Borrowed,
+    borrow::Cow::{Borrowed, Owned},
     env,
     error::Error,
     fs,
     io::{stdin, Read},
-    path::Path,
+    path::{Path, PathBuf},
+    process::Command as Process,
 };
 
-use ac_tools_rs::{
-    val::{self, base_path},
-    CustomError::*,
-    Message,
-};
-use dialoguer::{theme::ColorfulTheme, Input, Select};
+use ac_tools_rs::{judge, runner, CustomError::*, Message};
+use clap::Subcommand;
+use colored::Colorize;
 
-pub fn run() -> Result<(), Box<dyn Error>> {
-    let current_dir = env::current_dir()?;
-    let (current_dir_name, parent_dir_name) = val::path_name(&current_dir)?;
+use crate::service;
 
-    if !(parent_dir_name == "codeforces") {
-        println!(
-            "{} Codeforces以外のコンテストのテストケース作成はできません",
-            Message::Failed
-        );
-        return Ok(());
+/// 追加したテストケースのファイル名の接頭辞
+/// ojがダウンロードするサンプル(sample-N)と分けることで、ダウンロードし直しても上書きされない
+const CUSTOM_PREFIX: &str = "custom-";
+
+/// 一覧に表示する入出力の最大文字数
+const MAX_PREVIEW_LEN: usize = 30;
+
+// 問題はコンテスト環境内の問題ID(例: a)か、問題のURLで指定する
+#[derive(Debug, Subcommand)]
+#[allow(non_camel_case_types)]
+pub enum Command {
+    /// テストケースを追加する(入力と出力を標準入力から読み込む)
+    add { problem: String },
+
+    /// 追加したテストケースを削除する
+    remove {
+        problem: String,
+
+        /// 削除するテストケースの番号(省略時は最後に追加したもの)
+        number: Option<u32>,
+    },
+
+    /// サンプルと追加したテストケースの一覧を表示する
+    list { problem: String },
+
+    /// 追加したテストケースの入出力をエディタ($EDITOR)で編集する
+    edit {
+        problem: String,
+
+        /// 編集するテストケースの番号(省略時は最後に追加したもの)
+        number: Option<u32>,
+    },
+}
+
+pub fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::add { problem } => add(&testcase_path(&problem)?)?,
+        Command::remove { problem, number } => remove(&testcase_path(&problem)?, number)?,
+        Command::list { problem } => list(&testcase_path(&problem)?)?,
+        Command::edit { problem, number } => edit(&testcase_path(&problem)?, number)?,
     }
 
-    let problem_alphabet: String = Input::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!("{} 問題ID", Message::Input))
-        .interact_text()?;
-
-    let choices = &["1:追加", "2:削除"];
-    let choice = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt(format!(
-            "{} コンテストの種類を選択してください",
-            Message::Question
-        ))
-        .items(choices)
-        .default(0)
-        .interact()?;
-
-    // add
-    if choice == 0 {
-        let testcase_path_str = format!(
-            "{}/test/codeforces/{}/{}",
-            base_path()?,
-            current_dir_name,
-            problem_alphabet
-        );
-        let testcase_path = Path::new(&testcase_path_str);
+    return Ok(());
+}
 
-        if !testcase_path.is_dir() {
-            fs::create_dir_all(testcase_path)?;
-        }
+/// テストと同じ変換でテストケースのディレクトリを求める
+fn testcase_path(problem: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let (testcase_path_str, _) = if problem.starts_with("http") {
+        judge::url_converter(problem)?
+    } else {
+        service::contest::resolve(problem)?
+    };
 
-        // 現在のファイル数を数える
-        let cnt = fs::read_dir(&testcase_path)?.count();
+    return Ok(PathBuf::from(testcase_path_str));
+}
 
-        let input_file_name = format!("sample-{}.in", cnt / 2 + 1);
-        let output_file_name = format!("sample-{}.out", cnt / 2 + 1);
+/// 追加したテストケースの番号(昇順)
+fn custom_numbers(testcase_path: &Path) -> Result<Vec<u32>, Box<dyn Error>> {
+    let mut res = Vec::new();
+    if !testcase_path.is_dir() {
+        return Ok(res);
+    }
 
-        // write input
+    for file in fs::read_dir(testcase_path)? {
+        let path = file?.path();
+        if path.extension().is_none_or(|ext| ext != "in") {
+            continue;
+        }
+        if let Some(number) = path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(CUSTOM_PREFIX))
+            .and_then(|number| number.parse().ok())
         {
-            println!("{}", "標準入力を入力してください(Ctrl+Dで終了)");
-            let mut input = Vec::new();
-            stdin().read_to_end(&mut input)?;
-
-            fs::write(testcase_path.join(input_file_name), input)?;
+            res.push(number);
         }
+    }
+    res.sort();
 
-        // write output
-        {
-            println!("{}", "標準出力を入力してください(Ctrl+Dで終了)");
-            let mut input = Vec::new();
-            stdin().read_to_end(&mut input)?;
+    return Ok(res);
+}
 
-            fs::write(testcase_path.join(output_file_name), input)?;
-        }
+/// 番号が省略された場合は最後に追加したもの
+fn select_number(testcase_path: &Path, number: Option<u32>) -> Result<u32, Box<dyn Error>> {
+    let numbers = custom_numbers(testcase_path)?;
+    let number = match number {
+        Some(number) => number,
+        None => *numbers
+            .last()
+            .ok_or(FileNotfoundError(Borrowed("testcase")))?,
+    };
+
+    if !numbers.contains(&number) {
+        return Err(Box::new(FileNotfoundError(Owned(format!(
+            "{}{}.in",
+            CUSTOM_PREFIX, number
+        )))));
+    }
+
+    return Ok(number);
+}
+
+fn case_path(testcase_path: &Path, number: u32, ext: &str) -> PathBuf {
+    return testcase_path.join(format!("{}{}.{}", CUSTOM_PREFIX, number, ext));
+}
+
+fn add(testcase_path: &Path) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(testcase_path)?;
+
+    // 削除して番号が空いていても、最後の番号の次にする
+    let number = custom_numbers(testcase_path)?
+        .last()
+        .map_or(1, |last| last + 1);
+
+    // write input
+    {
+        println!("標準入力を入力してください(Ctrl+Dで終了)");
+        let mut input = Vec::new();
+        stdin().read_to_end(&mut input)?;
 
-        println!("{} テストケースの作成に成功しました", Message::Success);
+        fs::write(case_path(testcase_path, number, "in"), input)?;
     }
 
-    // delete
-    if choice == 1 {
-        let testcase_path_str = format!(
-            "{}/test/codeforces/{}/{}",
-            base_path()?,
-            current_dir_name,
-            problem_alphabet
-        );
-        let testcase_path = Path::new(&testcase_path_str);
+    // write output
+    {
+        println!("標準出力を入力してください(Ctrl+Dで終了)");
+        let mut input = Vec::new();
+        stdin().read_to_end(&mut input)?;
 
-        if !testcase_path.is_dir() || fs::read_dir(&testcase_path)?.count() == 0 {
-            return Err(Box::new(FileNotfoundError(Borrowed("testcase"))));
-        }
+        fs::write(case_path(testcase_path, number, "out"), input)?;
+    }
 
-        let testcase_files = fs::read_dir(&testcase_path)?;
-        let cnt_str = (fs::read_dir(&testcase_path)?.count() / 2).to_string();
-
-        // 最後に追加されたテストケースの入出力のみ削除する
-        for file in testcase_files {
-            let file_name = file?.file_name();
-            let file_name_str = file_name.to_str().ok_or(InvalidUnicodeError)?;
-            if file_name_str.contains(&cnt_str) {
-                println!("{} {}を削除しています...", Message::Info, file_name_str);
-                fs::remove_file(testcase_path.join(file_name))?;
-            }
+    println!(
+        "{} テストケース({}{})の作成に成功しました",
+        Message::Success,
+        CUSTOM_PREFIX,
+        number
+    );
+
+    return Ok(());
+}
+
+fn remove(testcase_path: &Path, number: Option<u32>) -> Result<(), Box<dyn Error>> {
+    let number = select_number(testcase_path, number)?;
+
+    for ext in ["in", "out"] {
+        let path = case_path(testcase_path, number, ext);
+        if path.is_file() {
+            println!("{} {}を削除しています...", Message::Info, path.display());
+            fs::remove_file(path)?;
         }
+    }
 
-        println!("{} テストケースの削除に成功しました", Message::Success);
+    println!("{} テストケースの削除に成功しました", Message::Success);
+
+    return Ok(());
+}
+
+fn list(testcase_path: &Path) -> Result<(), Box<dyn Error>> {
+    let cases = runner::cases(testcase_path).unwrap_or_default();
+    if cases.is_empty() {
+        println!("{} テストケースがありません", Message::Info);
+        return Ok(());
     }
 
+    for case in cases {
+        let input = fs::read_to_string(&case.input)?;
+        let output = match &case.expected {
+            Some(expected) => fs::read_to_string(expected)?,
+            None => String::new(),
+        };
+        let name = if case.name.starts_with(CUSTOM_PREFIX) {
+            case.name.bright_cyan()
+        } else {
+            case.name.normal()
+        };
+
+        println!(
+            "{:<12} {} {} {}",
+            name,
+            preview(&input),
+            "->".bright_black(),
+            preview(&output)
+        );
+    }
+
+    return Ok(());
+}
+
+/// 改行を空白にして、長ければ省略する
+fn preview(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.chars().count() <= MAX_PREVIEW_LEN {
+        return text;
+    }
+
+    return text.chars().take(MAX_PREVIEW_LEN).collect::<String>() + "...";
+}
+
+fn edit(testcase_path: &Path, number: Option<u32>) -> Result<(), Box<dyn Error>> {
+    let number = select_number(testcase_path, number)?;
+
+    // "code -w"のように引数が含まれている場合もある
+    let editor = env::var("EDITOR").unwrap_or(String::from("vi"));
+    let mut editor_args = editor.split_whitespace();
+    let editor_cmd = editor_args.next().ok_or(EnvVarError(Borrowed("EDITOR")))?;
+
+    Process::new(editor_cmd)
+        .args(editor_args)
+        .arg(case_path(testcase_path, number, "in"))
+        .arg(case_path(testcase_path, number, "out"))
+        .status()?;
+
     return Ok(());
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::testcase::preview;
+
+    #[test]
+    fn test_preview() {
+        assert_eq!(preview("3\n1 2 3\n"), "3 1 2 3");
+        assert_eq!(preview(&"1 ".repeat(20)), format!("{}...", "1 ".repeat(15)));
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use ac_tools_rs::{
+    compile,
+    header::{self, Profile, Variant},
+    judge,
+    runner::{self, Options},
+    Message,
+};
+use chrono::Local;
+
+use crate::service;
+
+/// ファイルの更新を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// 最後の更新からこの時間だけ変更がなければテストする
+/// エディタが複数回に分けて書き込む場合に、途中の状態でコンパイルしないようにする
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+pub fn run(
+    problem_id: String,
+    auto: bool,
+    debug: bool,
+    jobs: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
+    let (testcase_path_str, problem_url) = service::contest::resolve(&problem_id)?;
+    let variant = header::profile(if debug {
+        Profile::TestDebug
+    } else {
+        Profile::Test
+    })?;
+
+    // サンプルのダウンロードや問題の情報の取得は最初の一回だけ行う
+    let mut options = match judge::prepare(&testcase_path_str, &problem_url)? {
+        Some(options) => options,
+        None => {
+            println!(
+                "{} サンプルもテストケースも存在しない問題です",
+                Message::Failed
+            );
+            return Ok(());
+        }
+    };
+    options.jobs = jobs;
+
+    println!(
+        "{} {}.cppとincludeしているファイルの変更を監視しています(Ctrl+Cで終了)",
+        Message::Info,
+        problem_id
+    );
+
+    loop {
+        // テスト中に保存された場合も検知できるように、先に更新日時を記録しておく
+        let files = watched_files(&problem_id, &variant);
+        let before = snapshot(&files);
+
+        let passed = test(&problem_id, &variant, &testcase_path_str, &options)?;
+        judge::write_latest(&problem_url, &problem_id)?;
+
+        if passed && auto {
+            judge::submit(&problem_url, &problem_id)?;
+            return Ok(());
+        }
+
+        wait_for_change(&files, before);
+    }
+}
+
+/// ソースとincludeしているファイル
+fn watched_files(problem_id: &str, variant: &Variant) -> Vec<PathBuf> {
+    match compile::sources(problem_id, variant) {
+        Ok(files) => return files,
+        // ソースが一時的に存在しない場合など
+        Err(_) => return vec![PathBuf::from(format!("{}.cpp", problem_id))],
+    }
+}
+
+fn snapshot(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    return files
+        .iter()
+        .map(|file| {
+            fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect();
+}
+
+fn wait_for_change(files: &[PathBuf], before: Vec<Option<SystemTime>>) {
+    let mut current = before.clone();
+    while current == before {
+        thread::sleep(POLL_INTERVAL);
+        current = snapshot(files);
+    }
+
+    // 書き込みが落ち着くまで待つ
+    loop {
+        thread::sleep(DEBOUNCE);
+        let next = snapshot(files);
+        if next == current {
+            return;
+        }
+        current = next;
+    }
+}
+
+/// コンパイルして全てのサンプルを実行し、結果を1行で表示する
+fn test(
+    problem_id: &str,
+    variant: &Variant,
+    testcase_path_str: &str,
+    options: &Options,
+) -> Result<bool, Box<dyn Error>> {
+    let time = Local::now().format("%H:%M:%S");
+
+    // コンパイルエラーの要約はcompile::buildで表示される
+    if let Err(error) = compile::build(problem_id, variant, false) {
+        println!("{} {} {:?}", Message::Failed, time, error);
+        return Ok(false);
+    }
+
+    let outcomes = runner::run_cases(
+        Path::new(&format!("./{}", problem_id)),
+        &format!("{}.cpp", problem_id),
+        &runner::cases(Path::new(testcase_path_str))?,
+        options,
+    )?;
+
+    let passed = outcomes.iter().filter(|outcome| outcome.passed()).count();
+    let verdicts: Vec<String> = outcomes
+        .iter()
+        .map(|outcome| outcome.verdict.to_string())
+        .collect();
+    let max_elapsed = outcomes
+        .iter()
+        .map(|outcome| outcome.elapsed.as_millis())
+        .max()
+        .unwrap_or_default();
+    // 最初に失敗したケースだけ、どのケースで何が起きたかを付け加える
+    let detail =
+        outcomes
+            .iter()
+            .find(|outcome| !outcome.passed())
+            .map_or(String::new(), |outcome| match &outcome.report {
+                Some(report) => format!(" ({}: {})", outcome.case.name, report.short_kind()),
+                None => format!(" ({}: {})", outcome.case.name, outcome.verdict),
+            });
+
+    let all_passed = passed == outcomes.len();
+    println!(
+        "{} {} {}/{} {} {}ms{}",
+        if all_passed {
+            Message::Success
+        } else {
+            Message::Failed
+        },
+        time,
+        passed,
+        outcomes.len(),
+        verdicts.join(" "),
+        max_elapsed,
+        detail
+    );
+
+    return Ok(all_passed);
+}
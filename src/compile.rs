@@ -0,0 +1,349 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, HashSet},
+    error::Error,
+    fmt,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::LazyLock,
+    time::SystemTime,
+};
+
+use colored::Colorize;
+use regex::Regex;
+use thiserror::Error;
+
+use crate::{
+    header::{self, Variant},
+    log,
+    val::base_path,
+    Message,
+};
+
+/// 残しておくビルドキャッシュの最大数
+const MAX_CACHE_ENTRIES: usize = 32;
+/// コンパイルエラーの要約に表示する最大数
+const MAX_ERRORS: usize = 5;
+
+/// GCC/Clangのエラーの行(例: a.cpp:5:3: error: 'x' was not declared in this scope)
+static ERROR_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?):(\d+):(\d+): (?:fatal )?error: (.*)$").unwrap());
+
+/// コンパイラが0以外の終了コードを返したときのエラー
+#[derive(Error)]
+#[error("CompileError")]
+pub struct CompileError {
+    /// コンパイルしたファイル(例: a.cpp, oj_all.hpp)
+    pub target: String,
+    /// コンパイラの出力(標準出力と標準エラー出力)
+    pub output: String,
+}
+
+impl fmt::Debug for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "CompileError: {}のコンパイルに失敗しました", self.target);
+    }
+}
+
+/// ヘッダを使ってコードをコンパイルする
+/// ソースとincludeしているファイル、コンパイルに使う引数が以前と同じなら、キャッシュしたバイナリを使う
+pub fn build(file_name: &str, variant: &Variant, rebuild: bool) -> Result<(), Box<dyn Error>> {
+    let (cmd, args_cow) = header::compile_commands(file_name, variant)?;
+
+    // Vec<Cow<str>>->Vec<&str>
+    let args: Vec<&str> = args_cow.iter().map(|i| i.as_ref()).collect();
+
+    let output_path = header::output_path(&args).unwrap_or_else(|| PathBuf::from(file_name));
+    let cache_path = cache_dir()?.join(format!("{:016x}", key(file_name, &cmd, &args)?));
+
+    if !rebuild && cache_path.is_file() {
+        fs::copy(&cache_path, &output_path)?;
+        // 古いものから削除するので、使ったキャッシュは新しくしておく
+        File::options()
+            .write(true)
+            .open(&cache_path)?
+            .set_modified(SystemTime::now())?;
+
+        println!(
+            "{} {}.cppに変更がないため、前回のバイナリを使用します",
+            Message::Info,
+            file_name
+        );
+        return Ok(());
+    }
+
+    // テンプレートのエラーなどで出力が長くなるので、まとめて受け取ってから要約する
+    let output = Command::new(&cmd)
+        .args(&args)
+        .stdin(Stdio::null())
+        .output()?;
+    let text = String::from_utf8_lossy(&output.stdout).into_owned()
+        + &String::from_utf8_lossy(&output.stderr);
+
+    if !output.status.success() {
+        // 前回のバイナリでテストや提出が行われないように削除する
+        let _ = fs::remove_file(&output_path);
+
+        report(&text, &forced_includes(&args));
+        return Err(Box::new(CompileError {
+            target: format!("{}.cpp", file_name),
+            output: text,
+        }));
+    }
+
+    // 警告はそのまま表示する
+    eprint!("{}", text);
+
+    // キャッシュできなくてもコンパイル自体は成功しているので続行する
+    if let Err(error) = store(&output_path, &cache_path) {
+        log::debug(&format!(
+            "ビルドキャッシュの保存に失敗しました: {:?}",
+            error
+        ));
+    }
+
+    return Ok(());
+}
+
+/// コンパイラの出力から取り出したエラー
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub message: String,
+    /// エラー箇所のコードと`^`の行
+    pub snippet: Vec<String>,
+}
+
+/// GCC/Clangの`file:line:col: error: message`形式のエラーを取り出す
+/// 同じ箇所の同じエラーは一つにまとめる
+pub fn parse_errors(output: &str) -> Vec<Diagnostic> {
+    let mut res: Vec<Diagnostic> = Vec::new();
+    let mut lines = output.lines().peekable();
+    while let Some(line) = lines.next() {
+        let Some(caps) = ERROR_LINE.captures(line) else {
+            continue;
+        };
+
+        // "    5 |   x = 1;"と"      |   ^"の行
+        let mut snippet = Vec::new();
+        while let Some(next) = lines.next_if(|next| next.starts_with(' ') && next.contains('|')) {
+            snippet.push(next.to_string());
+        }
+
+        let diagnostic = Diagnostic {
+            file: caps[1].to_string(),
+            line: caps[2].parse().unwrap_or_default(),
+            column: caps[3].parse().unwrap_or_default(),
+            message: caps[4].to_string(),
+            snippet,
+        };
+        if !res.iter().any(|other| {
+            other.file == diagnostic.file
+                && other.line == diagnostic.line
+                && other.column == diagnostic.column
+                && other.message == diagnostic.message
+        }) {
+            res.push(diagnostic);
+        }
+    }
+
+    return res;
+}
+
+/// -includeしたヘッダやシステムのヘッダの中のエラーを除く
+/// 全て除かれてしまう場合はそのまま返す
+fn user_errors(errors: Vec<Diagnostic>, headers: &[PathBuf]) -> Vec<Diagnostic> {
+    let headers: Vec<PathBuf> = headers
+        .iter()
+        .map(|header| header.canonicalize().unwrap_or(header.clone()))
+        .collect();
+    let is_noise = |error: &Diagnostic| {
+        let path = Path::new(&error.file);
+        return error.file.starts_with("/usr/")
+            || headers.contains(&path.canonicalize().unwrap_or(path.to_path_buf()));
+    };
+
+    if errors.iter().all(is_noise) {
+        return errors;
+    }
+
+    return errors
+        .into_iter()
+        .filter(|error| !is_noise(error))
+        .collect();
+}
+
+/// コンパイルエラーの要約を表示し、出力全体をファイルに保存する
+fn report(output: &str, headers: &[PathBuf]) {
+    let errors = user_errors(parse_errors(output), headers);
+
+    // リンクエラーなど、形式に当てはまらない場合はそのまま表示する
+    if errors.is_empty() {
+        eprint!("{}", output);
+    }
+    for error in errors.iter().take(MAX_ERRORS) {
+        eprintln!(
+            "{} {} {}",
+            format!("{}:{}:{}:", error.file, error.line, error.column).bold(),
+            "error:".red().bold(),
+            error.message
+        );
+        for line in &error.snippet {
+            eprintln!("{}", line);
+        }
+    }
+    if errors.len() > MAX_ERRORS {
+        println!(
+            "{} 他に{}件のエラーがあります",
+            Message::Info,
+            errors.len() - MAX_ERRORS
+        );
+    }
+
+    match save_log(output) {
+        Ok(path) => println!(
+            "{} コンパイラの出力全体は{}に保存されています",
+            Message::Info,
+            path.display()
+        ),
+        Err(error) => log::warn(&format!(
+            "コンパイラの出力の保存に失敗しました: {:?}",
+            error
+        )),
+    }
+}
+
+/// `{BASE_PATH}/log/compile.log`に保存する(直近のもののみ)
+fn save_log(output: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let log_dir = PathBuf::from(format!("{}/log", base_path()?));
+    fs::create_dir_all(&log_dir)?;
+
+    let log_path = log_dir.join("compile.log");
+    fs::write(&log_path, output)?;
+
+    return Ok(log_path);
+}
+
+/// `{BASE_PATH}/cache/build`
+fn cache_dir() -> Result<PathBuf, Box<dyn Error>> {
+    return Ok(PathBuf::from(format!("{}/cache/build", base_path()?)));
+}
+
+/// コンパイルに使う引数と、ソースと-includeされたヘッダ、そこから(推移的に)includeされているファイルの内容のハッシュ
+fn key(file_name: &str, cmd: &str, args: &[&str]) -> Result<u64, Box<dyn Error>> {
+    let mut hasher = DefaultHasher::new();
+    cmd.hash(&mut hasher);
+    args.hash(&mut hasher);
+
+    for file in inputs(file_name, args)? {
+        fs::read(&file)?.hash(&mut hasher);
+    }
+
+    return Ok(hasher.finish());
+}
+
+/// コンパイル結果に影響するファイル
+/// ソースと-includeされたヘッダ、そこから(推移的に)includeされている-I以下のファイル
+pub fn sources(file_name: &str, variant: &Variant) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let (_, args_cow) = header::compile_commands(file_name, variant)?;
+    let args: Vec<&str> = args_cow.iter().map(|i| i.as_ref()).collect();
+
+    return inputs(file_name, &args);
+}
+
+fn inputs(file_name: &str, args: &[&str]) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut roots = vec![PathBuf::from(format!("{}.cpp", file_name))];
+    roots.extend(forced_includes(args));
+
+    let include_dirs = header::include_dirs(args);
+    let mut visited = HashSet::new();
+    let mut files = Vec::new();
+    for root in roots.iter().filter(|root| root.is_file()) {
+        header::collect_includes(root, &include_dirs, &mut visited, &mut files)?;
+    }
+
+    return Ok(files);
+}
+
+/// -includeで指定されたファイル
+fn forced_includes(args: &[&str]) -> Vec<PathBuf> {
+    return args
+        .windows(2)
+        .filter(|pair| pair[0] == "-include")
+        .map(|pair| PathBuf::from(pair[1]))
+        .collect();
+}
+
+fn store(output_path: &Path, cache_path: &Path) -> Result<(), Box<dyn Error>> {
+    let dir = cache_dir()?;
+    fs::create_dir_all(&dir)?;
+
+    // 書きかけのバイナリが使われないように、別名でコピーしてから置き換える
+    let tmp_path = cache_path.with_extension("tmp");
+    fs::copy(output_path, &tmp_path)?;
+    fs::rename(&tmp_path, cache_path)?;
+
+    // 古いものから削除する
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        entries.push((entry.metadata()?.modified()?, entry.path()));
+    }
+    entries.sort_by_key(|(modified, _)| Reverse(*modified));
+    for (_, path) in entries.into_iter().skip(MAX_CACHE_ENTRIES) {
+        fs::remove_file(path)?;
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::compile::{forced_includes, parse_errors, user_errors};
+
+    #[test]
+    fn test_forced_includes() {
+        let args = ["a.cpp", "-include", "/base/all.hpp", "-I", "/base"];
+        assert_eq!(forced_includes(&args), vec![PathBuf::from("/base/all.hpp")]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let output = "\
+In file included from a.cpp:1:
+/base/library/header/all.hpp:3:1: error: expected ';' before 'int'
+a.cpp: In function 'int main()':
+a.cpp:5:3: error: 'x' was not declared in this scope
+    5 |   x = 1;
+      |   ^
+a.cpp:5:3: error: 'x' was not declared in this scope
+a.cpp:7:1: note: candidate: 'void f()'
+a.cpp:9:10: fatal error: library/b.hpp: No such file or directory
+";
+        let errors = parse_errors(output);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[1].line, 5);
+        assert_eq!(errors[1].snippet.len(), 2);
+        assert_eq!(
+            errors[2].message,
+            "library/b.hpp: No such file or directory"
+        );
+
+        let errors = user_errors(errors, &[PathBuf::from("/base/library/header/all.hpp")]);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].file, "a.cpp");
+
+        // ヘッダのエラーしかない場合は除かない
+        let errors = user_errors(
+            parse_errors("/base/library/header/all.hpp:3:1: error: x\n"),
+            &[PathBuf::from("/base/library/header/all.hpp")],
+        );
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    error::Error,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::{
+    compile::CompileError,
+    judge::{base_commands, header_commands, option_commands, CommandArgs},
+    log,
+    val::base_path,
+    Message,
+};
+
+/// プリコンパイルするヘッダの種類
+/// ヘッダを使うコードのコンパイルにも同じ引数を使う
+#[derive(Debug, Clone, Deserialize)]
+pub struct Variant {
+    /// `{BASE_PATH}/library/header/{name}.hpp`
+    pub name: String,
+    /// -DLOCALを付けるか
+    #[serde(default)]
+    pub local: bool,
+    /// cpp_options.txtの引数を付けるか
+    #[serde(default)]
+    pub options: bool,
+    /// 追加の引数
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// コマンドごとに使うヘッダ
+#[derive(Debug, Clone, Copy)]
+pub enum Profile {
+    /// ac test
+    Test,
+    /// ac test --debug
+    TestDebug,
+    /// ac debug
+    Debug,
+    /// ac nodebug
+    NoDebug,
+}
+
+#[derive(Debug, Deserialize)]
+struct Profiles {
+    #[serde(default = "default_test")]
+    test: String,
+    #[serde(default = "default_test_debug")]
+    test_debug: String,
+    #[serde(default = "default_debug")]
+    debug: String,
+    #[serde(default = "default_nodebug")]
+    nodebug: String,
+}
+
+impl Default for Profiles {
+    fn default() -> Self {
+        return Profiles {
+            test: default_test(),
+            test_debug: default_test_debug(),
+            debug: default_debug(),
+            nodebug: default_nodebug(),
+        };
+    }
+}
+
+fn default_test() -> String {
+    return String::from("oj_all");
+}
+
+fn default_test_debug() -> String {
+    return String::from("oj_nodebug_all");
+}
+
+fn default_debug() -> String {
+    return String::from("debug_all");
+}
+
+fn default_nodebug() -> String {
+    return String::from("nodebug_all");
+}
+
+/// `{BASE_PATH}/setting/headers.toml`の内容
+#[derive(Debug, Deserialize)]
+struct Setting {
+    #[serde(default = "default_variants")]
+    header: Vec<Variant>,
+    #[serde(default)]
+    profile: Profiles,
+}
+
+impl Variant {
+    fn new(name: &str, local: bool, options: bool, args: &[&str]) -> Variant {
+        return Variant {
+            name: name.to_string(),
+            local,
+            options,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        };
+    }
+}
+
+/// headers.tomlがない場合に使うヘッダ
+fn default_variants() -> Vec<Variant> {
+    return vec![
+        Variant::new("all", true, true, &[]),
+        Variant::new("debug_all", true, false, &["-g"]),
+        Variant::new("nodebug_all", true, false, &[]),
+        Variant::new("oj_all", false, true, &[]),
+        Variant::new("oj_nodebug_all", false, false, &[]),
+    ];
+}
+
+fn setting() -> Result<Setting, Box<dyn Error>> {
+    let path = format!("{}/setting/headers.toml", base_path()?);
+    match fs::read_to_string(path) {
+        Ok(text) => return Ok(toml::from_str(&text)?),
+        Err(_) => {
+            return Ok(Setting {
+                header: default_variants(),
+                profile: Profiles::default(),
+            })
+        }
+    }
+}
+
+pub fn variants() -> Result<Vec<Variant>, Box<dyn Error>> {
+    return Ok(setting()?.header);
+}
+
+/// コマンドで使うヘッダ
+pub fn profile(profile: Profile) -> Result<Variant, Box<dyn Error>> {
+    let setting = setting()?;
+    let name = match profile {
+        Profile::Test => setting.profile.test,
+        Profile::TestDebug => setting.profile.test_debug,
+        Profile::Debug => setting.profile.debug,
+        Profile::NoDebug => setting.profile.nodebug,
+    };
+
+    return setting
+        .header
+        .into_iter()
+        .find(|variant| variant.name == name)
+        .ok_or(format!("headers.tomlに{}が定義されていません", name).into());
+}
+
+/// ヘッダを使ってコードをコンパイルするコマンド
+/// 使用するヘッダが古ければ先にプリコンパイルし直す
+pub fn compile_commands<'a>(
+    file_name: &'a str,
+    variant: &'a Variant,
+) -> Result<CommandArgs<'a>, Box<dyn Error>> {
+    ensure(variant)?;
+
+    let (cmd, mut args) = base_commands(file_name, &variant.name, variant.local)?;
+    if variant.options {
+        args.append(&mut option_commands()?);
+    }
+    args.extend(variant.args.iter().map(|arg| arg.as_str().into()));
+
+    return Ok((cmd, args));
+}
+
+/// ヘッダをプリコンパイルするコマンド
+pub fn commands(variant: &Variant) -> Result<CommandArgs<'_>, Box<dyn Error>> {
+    let (cmd, mut args) = header_commands(&variant.name, variant.local)?;
+    if variant.options {
+        args.append(&mut option_commands()?);
+    }
+    args.extend(variant.args.iter().map(|arg| arg.as_str().into()));
+
+    return Ok((cmd, args));
+}
+
+pub(crate) fn header_path(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    return Ok(PathBuf::from(format!(
+        "{}/library/header/{}.hpp",
+        base_path()?,
+        name
+    )));
+}
+
+/// 前回プリコンパイルしたときのフィンガープリントを保存するファイル
+fn stamp_path(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    return Ok(PathBuf::from(format!(
+        "{}/library/header/{}.hpp.stamp",
+        base_path()?,
+        name
+    )));
+}
+
+/// プリコンパイル済みヘッダの出力先(-oの引数)
+pub fn output_path(args: &[&str]) -> Option<PathBuf> {
+    let index = args.iter().position(|arg| *arg == "-o")?;
+    return args.get(index + 1).map(PathBuf::from);
+}
+
+/// ヘッダと、そこから(推移的に)includeされているファイルの内容、コンパイルに使う引数のハッシュ
+pub fn fingerprint(variant: &Variant) -> Result<u64, Box<dyn Error>> {
+    let (cmd, args) = commands(variant)?;
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_ref()).collect();
+
+    let mut hasher = DefaultHasher::new();
+    cmd.hash(&mut hasher);
+    args.hash(&mut hasher);
+
+    let mut files = Vec::new();
+    collect_includes(
+        &header_path(&variant.name)?,
+        &include_dirs(&args),
+        &mut HashSet::new(),
+        &mut files,
+    )?;
+    for file in files {
+        file.hash(&mut hasher);
+        fs::read(&file)?.hash(&mut hasher);
+    }
+
+    return Ok(hasher.finish());
+}
+
+/// -Iで指定されたディレクトリ
+pub(crate) fn include_dirs(args: &[&str]) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        if *arg == "-I" {
+            if let Some(dir) = args.get(i + 1) {
+                dirs.push(PathBuf::from(dir));
+            }
+        } else if let Some(dir) = arg.strip_prefix("-I") {
+            dirs.push(PathBuf::from(dir));
+        }
+    }
+
+    return dirs;
+}
+
+/// includeされているファイルを深さ優先で集める
+/// システムのヘッダなど、見つからないファイルは無視する
+pub(crate) fn collect_includes(
+    path: &Path,
+    include_dirs: &[PathBuf],
+    visited: &mut HashSet<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let path = path.canonicalize()?;
+    if !visited.insert(path.clone()) {
+        return Ok(());
+    }
+    files.push(path.clone());
+
+    let re = Regex::new(r#"^\s*#\s*include\s*([<"])([^>"]+)[>"]"#)?;
+    let code = fs::read_to_string(&path)?;
+    for line in code.lines() {
+        let Some(caps) = re.captures(line) else {
+            continue;
+        };
+
+        // "..."はincludeしているファイルのディレクトリからも探す
+        let mut candidates = Vec::new();
+        if &caps[1] == "\"" {
+            if let Some(parent) = path.parent() {
+                candidates.push(parent.join(&caps[2]));
+            }
+        }
+        candidates.extend(include_dirs.iter().map(|dir| dir.join(&caps[2])));
+
+        if let Some(found) = candidates.into_iter().find(|candidate| candidate.is_file()) {
+            collect_includes(&found, include_dirs, visited, files)?;
+        }
+    }
+
+    return Ok(());
+}
+
+/// プリコンパイルし直す必要があるか
+pub fn is_stale(variant: &Variant) -> Result<bool, Box<dyn Error>> {
+    let (_, args) = commands(variant)?;
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_ref()).collect();
+    if output_path(&args).is_some_and(|path| !path.is_file()) {
+        return Ok(true);
+    }
+
+    let stamp = match fs::read_to_string(stamp_path(&variant.name)?) {
+        Ok(stamp) => stamp,
+        Err(_) => return Ok(true),
+    };
+
+    return Ok(stamp.trim() != format!("{:x}", fingerprint(variant)?));
+}
+
+/// プリコンパイルして、成功したらフィンガープリントを保存する
+pub fn build(variant: &Variant) -> Result<(), Box<dyn Error>> {
+    let (cmd, args) = commands(variant)?;
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_ref()).collect();
+
+    // 並列にコンパイルすると出力が混ざるので、まとめて返す
+    let output = Command::new(&cmd)
+        .args(&args)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        // 書きかけのプリコンパイル済みヘッダが使われないように削除する
+        if let Some(path) = output_path(&args) {
+            let _ = fs::remove_file(path);
+        }
+        let _ = fs::remove_file(stamp_path(&variant.name)?);
+
+        return Err(Box::new(CompileError {
+            target: format!("{}.hpp", variant.name),
+            output: String::from_utf8_lossy(&output.stdout).into_owned()
+                + &String::from_utf8_lossy(&output.stderr),
+        }));
+    }
+
+    fs::write(
+        stamp_path(&variant.name)?,
+        format!("{:x}", fingerprint(variant)?),
+    )?;
+
+    return Ok(());
+}
+
+/// 使用するプリコンパイル済みヘッダが古ければ生成し直す
+/// プリコンパイルを使っていない環境では何もしない
+fn ensure(variant: &Variant) -> Result<(), Box<dyn Error>> {
+    let name = &variant.name;
+    if !header_path(name)?.is_file() {
+        return Ok(());
+    }
+
+    let stale = match is_stale(variant) {
+        Ok(stale) => stale,
+        Err(error) => {
+            // cpp_header.txtがない場合など
+            log::debug(&format!(
+                "{}.hppの確認をスキップしました: {:?}",
+                name, error
+            ));
+            return Ok(());
+        }
+    };
+    if !stale {
+        return Ok(());
+    }
+
+    println!(
+        "{} {}.hppが更新されているので、プリコンパイルし直します",
+        Message::Warning,
+        name
+    );
+    // 失敗してもgccはプリコンパイル済みヘッダを使わずにコンパイルするので続行する
+    if let Err(error) = build(variant) {
+        println!("{} {:?}", Message::Warning, error);
+        if let Some(error) = error.downcast_ref::<CompileError>() {
+            eprint!("{}", error.output);
+        }
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::header::{include_dirs, output_path, Setting};
+
+    #[test]
+    fn test_include_dirs() {
+        let args = ["-I", "/base", "-I/lib", "-O2"];
+        assert_eq!(
+            include_dirs(&args),
+            vec![PathBuf::from("/base"), PathBuf::from("/lib")]
+        );
+        assert_eq!(
+            output_path(&["a.hpp", "-o", "a.hpp.gch"]),
+            Some(PathBuf::from("a.hpp.gch"))
+        );
+    }
+
+    #[test]
+    fn test_setting() {
+        let setting: Setting = toml::from_str(
+            "[[header]]\nname = \"asan\"\nargs = [\"-O0\"]\n[profile]\ndebug = \"asan\"\n",
+        )
+        .unwrap();
+        assert_eq!(setting.header.len(), 1);
+        assert!(!setting.header[0].local);
+        assert_eq!(setting.profile.debug, "asan");
+        assert_eq!(setting.profile.test, "oj_all");
+
+        let setting: Setting = toml::from_str("").unwrap();
+        assert_eq!(setting.header.len(), 5);
+    }
+}
@@ -23,6 +23,10 @@ g() {
 h() {
     ac contest h $@
 }
+# 任意の問題ID(例: p ex)
+p() {
+    ac contest $@
+}
 
 # atcoder
 abc() {
@@ -23,6 +23,10 @@ end
 function h
     ac contest h $argv
 end
+# 任意の問題ID(例: p ex)
+function p
+    ac contest $argv
+end
 
 # atcoder
 function abc
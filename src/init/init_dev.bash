@@ -23,6 +23,10 @@ _g() {
 _h() {
     cargo run --bin ac contest h $@
 }
+# 任意の問題ID(例: p ex)
+_p() {
+    cargo run --bin ac contest $@
+}
 
 # atcoder
 _abc() {
@@ -23,6 +23,10 @@ end
 function _h
     cargo run --bin ac contest h $argv
 end
+# 任意の問題ID(例: p ex)
+function _p
+    cargo run --bin ac contest $argv
+end
 
 # atcoder
 function _abc
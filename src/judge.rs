@@ -2,15 +2,40 @@ use std::{
     borrow::Cow::{self, Borrowed, Owned},
     env,
     error::Error,
-    fs::{self, File},
-    io::{BufRead, BufReader, Read},
+    fs,
+    io::{BufRead, BufReader},
     path::Path,
     process::{Command, Output, Stdio},
+    sync::LazyLock,
+    time::Duration,
 };
 
 use regex::Regex;
 
-use crate::{submission, val::base_path, CustomError::*, Message};
+use crate::{
+    compile,
+    header::{self, Profile},
+    meta::{self, Meta},
+    runner, submission,
+    val::base_path,
+    CustomError::*,
+    Message,
+};
+
+/// 例: abc123
+static AC_CONTEST: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(abc|arc|agc)([0-9]+)$").unwrap());
+
+/// 例: https://codeforces.com/contest/1950/problem/A
+static CF_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"codeforces\.com/contest/([0-9]+)/problem/([0-9A-Za-z]+)").unwrap()
+});
+/// no/{問題番号}と{問題ID}の二種類がある
+static YUKI_URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"yukicoder\.me/problems/(no/)?([0-9]+)").unwrap());
+
+/// (実行コマンド, 引数)
+pub type CommandArgs<'a> = (String, Vec<Cow<'a, str>>);
 
 pub fn run(
     testcase_path_str: String,
@@ -18,117 +43,146 @@ pub fn run(
     file_name: &str,
     auto: bool,
     debug: bool,
+    rebuild: bool,
+    jobs: Option<usize>,
 ) -> Result<(), Box<dyn Error>> {
     // コンパイル
     {
-        // 実行コマンドの引数生成
-        let cmd;
-        let mut args_cow;
-        if debug {
-            (cmd, args_cow) = base_commands(&file_name, "oj_nodebug_all", false)?;
+        let variant = header::profile(if debug {
+            Profile::TestDebug
         } else {
-            (cmd, args_cow) = base_commands(&file_name, "oj_all", false)?;
-            args_cow.append(&mut option_commands()?);
-        }
-
-        // Vec<Cow<str>>->Vec<&str>
-        let args = args_cow.iter().map(|i| i.as_ref()).collect();
-
-        // 実行
-        subprocess(&cmd, args)?;
+            Profile::Test
+        })?;
+        compile::build(file_name, &variant, rebuild)?;
     }
 
     // ジャッジ
-    let res;
+    let passed;
     {
-        // 少数判定用にサンプル取得
-        let testcase_path = Path::new(&testcase_path_str);
-        if !testcase_path.is_dir() {
-            let args = vec!["d", &problem_url, "-d", &testcase_path_str];
-            subprocess("oj", args)?;
-        }
-
-        // サンプル読み込み
-        let mut testcase_str = String::new();
-        match File::open(testcase_path.join("sample-1.out")) {
-            Ok(mut testcase_file) => {
-                testcase_file.read_to_string(&mut testcase_str)?;
-            }
-            Err(_) => {
+        let mut options = match prepare(&testcase_path_str, &problem_url)? {
+            Some(options) => options,
+            None => {
                 // サンプルがない問題も存在する
                 // エラーメッセージはojのほうで出力されるので省略
-
-                // URL書き出し
-                fs::write(
-                    format!("{}/library/url_latest.txt", base_path()?),
-                    (problem_url + "\n" + &file_name).as_bytes(),
-                )?;
-
+                write_latest(&problem_url, file_name)?;
                 return Ok(());
             }
-        }
+        };
 
-        // スペース区切りで読み込み
-        let testcase_str_vec: Vec<&str> = testcase_str.trim().split_whitespace().collect();
-
-        // テスト用コマンド引数生成
-        let file_name_path = format!("./{}", file_name);
-        let mut args = vec![
-            "t",
-            "-N",
-            "-c",
-            &file_name_path,
-            "-d",
-            &testcase_path_str,
-            "-D",
-        ];
-
-        // 少数判定
-        let re = Regex::new(r"^[+-]?[0-9]+\.[0-9]+$")?;
-        if re.is_match(testcase_str_vec.get(0).ok_or(IndexError)?) {
-            args.push("-e");
-            args.push("1e-6");
-        }
+        options.jobs = jobs;
 
         // ジャッジ実行
-        res = subprocess("oj", args)?;
+        // ケースごとに標準エラー出力を受け取り、サニタイザの報告を検出する
+        let cases = runner::cases(Path::new(&testcase_path_str))?;
+        let outcomes = runner::run(
+            Path::new(&format!("./{}", file_name)),
+            &format!("{}.cpp", file_name),
+            &cases,
+            &options,
+        )?;
+        passed = runner::summary(&outcomes);
     }
 
     // 提出/submit
-    if res.status.success() && auto {
-        // atcoderのみ対応
-        if problem_url.contains("atcoder.jp") && env::var("AC_USE_OJ").is_err() {
-            // URLからコンテスト情報の取り出し(例:"abc123_a")
-            let problem_data_vec: Vec<&str> = problem_url.split("/").collect();
-            let mut problem_data = problem_data_vec
-                .get(problem_data_vec.len() - 1)
-                .ok_or(IndexError)?
-                .split("_");
-
-            submission::run(
-                problem_data.next().ok_or(IndexError)?,
-                problem_data.next().ok_or(IndexError)?,
-                env::current_dir()?.join(format!("{}.cpp", &file_name)),
-                0,
-            )?;
+    if passed && auto {
+        submit(&problem_url, file_name)?;
+    }
+
+    write_latest(&problem_url, file_name)?;
+
+    return Ok(());
+}
+
+/// サンプルがなければダウンロードし、ジャッジの設定を求める
+/// サンプルも追加したテストケースも存在しない問題ではNoneを返す
+pub fn prepare(
+    testcase_path_str: &str,
+    problem_url: &str,
+) -> Result<Option<runner::Options>, Box<dyn Error>> {
+    // 少数判定用にサンプル取得
+    // 追加したテストケースだけが存在する場合もダウンロードする
+    // ojが対応していないサイトでも追加したテストケースでテストできるように続行する
+    let testcase_path = Path::new(testcase_path_str);
+    if !runner::has_samples(testcase_path) {
+        let args = vec!["d", problem_url, "-d", testcase_path_str];
+        match subprocess("oj", args) {
+            Ok(output) if output.status.success() => {}
+            Ok(_) => println!("{} サンプルのダウンロードに失敗しました", Message::Warning),
+            Err(error) => println!(
+                "{} サンプルのダウンロードに失敗しました({})",
+                Message::Warning,
+                error
+            ),
+        }
+    }
+
+    // テストケース読み込み
+    let cases = runner::cases(testcase_path).unwrap_or_default();
+    if cases.is_empty() {
+        return Ok(None);
+    }
+
+    // 実行時間制限/メモリ制限
+    // 取得できなくても制限なしでテストできるので続行する
+    let problem_meta = meta::get(testcase_path_str, problem_url).unwrap_or_else(|error| {
+        println!(
+            "{} 問題の情報の取得に失敗しました({})",
+            Message::Warning,
+            error
+        );
+        Meta::default()
+    });
+
+    return Ok(Some(runner::Options {
+        time_limit: problem_meta.time_limit_ms.map(Duration::from_millis),
+        memory_limit_mb: problem_meta.memory_limit_mb,
+        // 少数判定
+        error: float_error(&cases)?,
+        ..Default::default()
+    }));
+}
+
+/// 出力が用意されている最初のテストケースから少数判定の誤差を求める
+fn float_error(cases: &[runner::Case]) -> Result<Option<f64>, Box<dyn Error>> {
+    return match cases.iter().find_map(|case| case.expected.as_ref()) {
+        Some(expected) => Ok(runner::float_error(&fs::read_to_string(expected)?)),
+        None => Ok(None),
+    };
+}
+
+/// カレントディレクトリの`{file_name}.cpp`を提出する
+pub fn submit(problem_url: &str, file_name: &str) -> Result<(), Box<dyn Error>> {
+    // atcoderのみ対応
+    if problem_url.contains("atcoder.jp") && env::var("AC_USE_OJ").is_err() {
+        let (contest_id, task_screen_name) = submission::atcoder_task(problem_url)?;
+
+        submission::run(
+            contest_id,
+            task_screen_name,
+            env::current_dir()?.join(format!("{}.cpp", &file_name)),
+            0,
+        )?;
+    } else {
+        if problem_url.contains("codeforces.com") {
+            println!(
+                "{} Codeforcesへの自動提出には対応していません",
+                Message::Failed
+            );
         } else {
-            if problem_url.contains("codeforces.com") {
-                println!(
-                    "{} Codeforcesへの自動提出には対応していません",
-                    Message::Failed
-                );
-            } else {
-                let file_name_cpp = format!("{}.cpp", file_name);
-                let args = vec!["s", &problem_url, &file_name_cpp, "-y", "-w", "0"];
-                subprocess("oj", args)?;
-            }
+            let file_name_cpp = format!("{}.cpp", file_name);
+            let args = vec!["s", problem_url, &file_name_cpp, "-y", "-w", "0"];
+            subprocess("oj", args)?;
         }
     }
 
-    // URL書き出し
+    return Ok(());
+}
+
+/// `ac submit`で提出するために、直近にテストした問題を書き出す
+pub fn write_latest(problem_url: &str, file_name: &str) -> Result<(), Box<dyn Error>> {
     fs::write(
         format!("{}/library/url_latest.txt", base_path()?),
-        (problem_url + "\n" + &file_name).as_bytes(),
+        format!("{}\n{}", problem_url, file_name).as_bytes(),
     )?;
 
     return Ok(());
@@ -138,7 +192,7 @@ pub fn base_commands<'a>(
     file_name: &'a str,
     header_name: &str,
     local: bool,
-) -> Result<(String, Vec<Cow<'a, str>>), Box<dyn Error>> {
+) -> Result<CommandArgs<'a>, Box<dyn Error>> {
     // 存在確認
     {
         let testcase_path = env::current_dir()?;
@@ -151,7 +205,7 @@ pub fn base_commands<'a>(
     }
 
     let commands_file = fs::File::open(format!("{}/setting/cpp.txt", base_path()?))
-        .or_else(|_| Err(Box::new(FileNotfoundError(Borrowed("cpp.txt")))))?;
+        .map_err(|_| Box::new(FileNotfoundError(Borrowed("cpp.txt"))))?;
     let commands_file_buf = BufReader::new(commands_file).lines();
 
     let base_path_str = base_path()?;
@@ -179,7 +233,7 @@ pub fn base_commands<'a>(
 
 pub fn option_commands<'a>() -> Result<Vec<Cow<'a, str>>, Box<dyn Error>> {
     let options_file = fs::File::open(format!("{}/setting/cpp_options.txt", base_path()?))
-        .or_else(|_| Err(Box::new(FileNotfoundError(Borrowed("cpp_options.txt")))))?;
+        .map_err(|_| Box::new(FileNotfoundError(Borrowed("cpp_options.txt"))))?;
     let options_file_buf = BufReader::new(options_file).lines();
 
     let mut res = Vec::new();
@@ -190,10 +244,7 @@ pub fn option_commands<'a>() -> Result<Vec<Cow<'a, str>>, Box<dyn Error>> {
     return Ok(res);
 }
 
-pub fn header_commands(
-    file_name: &str,
-    local: bool,
-) -> Result<(String, Vec<Cow<str>>), Box<dyn Error>> {
+pub fn header_commands(file_name: &str, local: bool) -> Result<CommandArgs<'_>, Box<dyn Error>> {
     // 存在確認
     let header_path_str = format!("{}/library/header/{}.hpp", base_path()?, file_name);
     if !Path::new(&header_path_str).is_file() {
@@ -204,7 +255,7 @@ pub fn header_commands(
     }
 
     let header_file = fs::File::open(format!("{}/setting/cpp_header.txt", base_path()?))
-        .or_else(|_| Err(Box::new(FileNotfoundError(Borrowed("cpp_header.txt")))))?;
+        .map_err(|_| Box::new(FileNotfoundError(Borrowed("cpp_header.txt"))))?;
     let header_file_buf = BufReader::new(header_file).lines();
 
     let base_path_str = base_path()?;
@@ -233,6 +284,18 @@ pub fn ac_converter(
     contest_name: &str,
     contest_id: &str,
     problem_id: &str,
+) -> Result<(String, String), Box<dyn Error>> {
+    let task_screen_name = format!("{}{}_{}", contest_name, contest_id, problem_id);
+
+    return ac_task_converter(contest_name, contest_id, problem_id, &task_screen_name);
+}
+
+/// 問題IDとURL中の問題名が一致しない場合用(例: 問題ID"a"に対してabc001_1)
+pub fn ac_task_converter(
+    contest_name: &str,
+    contest_id: &str,
+    problem_id: &str,
+    task_screen_name: &str,
 ) -> Result<(String, String), Box<dyn Error>> {
     let testcase_path_str = format!(
         "{}/test/{}/{}/{}",
@@ -242,8 +305,36 @@ pub fn ac_converter(
         problem_id
     );
     let problem_url = format!(
-        "https://atcoder.jp/contests/{}{}/tasks/{}{}_{}",
-        contest_name, contest_id, contest_name, contest_id, problem_id
+        "https://atcoder.jp/contests/{}{}/tasks/{}",
+        contest_name, contest_id, task_screen_name
+    );
+
+    return Ok((testcase_path_str, problem_url));
+}
+
+/// コンテストIDとURL中の問題名から変換する(バーチャルコンテスト用)
+/// ABC/ARC/AGCの問題はac_converterと同じテストケースのディレクトリを使う
+pub fn ac_screen_converter(
+    contest_id: &str,
+    task_screen_name: &str,
+) -> Result<(String, String), Box<dyn Error>> {
+    // 例: abc123 + abc123_a
+    if let (Some(caps), Some(problem_id)) = (
+        AC_CONTEST.captures(contest_id),
+        task_screen_name.strip_prefix(&format!("{}_", contest_id)),
+    ) {
+        return ac_task_converter(&caps[1], &caps[2], problem_id, task_screen_name);
+    }
+
+    let testcase_path_str = format!(
+        "{}/test/atcoder/{}/{}",
+        base_path()?,
+        contest_id,
+        task_screen_name
+    );
+    let problem_url = format!(
+        "https://atcoder.jp/contests/{}/tasks/{}",
+        contest_id, task_screen_name
     );
 
     return Ok((testcase_path_str, problem_url));
@@ -274,16 +365,57 @@ pub fn yuki_converter(
 ) -> Result<(String, String), Box<dyn Error>> {
     let testcase_path_str = format!("{}/test/yukicoder/{}", base_path()?, problem_id);
 
-    let problem_url;
-    if is_contest {
-        problem_url = format!("https://yukicoder.me/problems/{}", problem_id);
+    let problem_url = if is_contest {
+        format!("https://yukicoder.me/problems/{}", problem_id)
     } else {
-        problem_url = format!("https://yukicoder.me/problems/no/{}", problem_id);
-    }
+        format!("https://yukicoder.me/problems/no/{}", problem_id)
+    };
 
     return Ok((testcase_path_str, problem_url));
 }
 
+/// 問題のURLから変換する
+/// 対応していないサイトの問題は`{BASE_PATH}/test/url/{URLから作った名前}`を使う
+pub fn url_converter(problem_url: &str) -> Result<(String, String), Box<dyn Error>> {
+    if problem_url.contains("atcoder.jp") {
+        let (contest_id, task_screen_name) = submission::atcoder_task(problem_url)?;
+        return ac_screen_converter(contest_id, task_screen_name);
+    }
+
+    if let Some(caps) = CF_URL.captures(problem_url) {
+        return cf_converter(&caps[1], &caps[2].to_ascii_lowercase());
+    }
+
+    if let Some(caps) = YUKI_URL.captures(problem_url) {
+        return yuki_converter(&caps[2], caps.get(1).is_none());
+    }
+
+    return Ok((
+        format!("{}/test/url/{}", base_path()?, url_dir_name(problem_url)),
+        problem_url.to_string(),
+    ));
+}
+
+/// 問題ごとにテストケースを分けるためのディレクトリ名
+/// 例: https://judge.yosupo.jp/problem/aplusb -> judge.yosupo.jp_problem_aplusb
+fn url_dir_name(problem_url: &str) -> String {
+    let url = problem_url
+        .split_once("://")
+        .map_or(problem_url, |(_, rest)| rest);
+
+    return url
+        .trim_end_matches('/')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+}
+
 pub fn subprocess(command: &str, args: Vec<&str>) -> Result<Output, Box<dyn Error>> {
     return Ok(Command::new(command)
         .args(args)
@@ -292,3 +424,43 @@ pub fn subprocess(command: &str, args: Vec<&str>) -> Result<Output, Box<dyn Erro
         .stderr(Stdio::inherit())
         .output()?);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use crate::{
+        judge::{float_error, url_dir_name},
+        runner,
+    };
+
+    #[test]
+    fn test_url_dir_name() {
+        assert_eq!(
+            url_dir_name("https://judge.yosupo.jp/problem/aplusb"),
+            "judge.yosupo.jp_problem_aplusb"
+        );
+        assert_ne!(
+            url_dir_name("https://onlinejudge.u-aizu.ac.jp/problems/ITP1_1_A"),
+            url_dir_name("https://onlinejudge.u-aizu.ac.jp/problems/ITP1_1_B")
+        );
+    }
+
+    #[test]
+    fn test_float_error_custom_only() {
+        // サンプルがダウンロードできず、追加したテストケースだけが存在する場合
+        let dir = env::temp_dir().join(format!("ac-judge-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("custom-1.in"), "1 2\n").unwrap();
+        fs::write(dir.join("custom-1.out"), "1.5\n").unwrap();
+
+        let cases = runner::cases(&dir).unwrap();
+        let error = float_error(&cases).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].name, "custom-1");
+        assert_eq!(error, Some(1e-6));
+        assert_eq!(float_error(&[]).unwrap(), None);
+    }
+}
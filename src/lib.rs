@@ -1,3 +1,5 @@
+#![allow(clippy::needless_return)]
+
 use std::{borrow::Cow, error::Error, fmt};
 
 use colored::Colorize;
@@ -5,8 +7,15 @@ use dialoguer::{theme::ColorfulTheme, Confirm};
 use thiserror::Error;
 use CustomError::*;
 
+pub mod compile;
+pub mod header;
 pub mod judge;
+pub mod log;
+pub mod meta;
+pub mod runner;
+pub mod sanitizer;
 pub mod submission;
+pub mod template;
 
 pub mod val {
     use std::{
@@ -110,7 +119,10 @@ impl Warning {
                 text += "コンテストの構成を実行しますか";
             }
             Self::Overwrite(s) => {
-                text += &format!("{} は既に存在しますが、上書きしますか", s);
+                text += &format!(
+                    "{} は既に存在しますが、更新しますか(既存のファイルは変更されません)",
+                    s
+                );
             }
             Self::Precompile => {
                 text += "プリコンパイル済みヘッダを生成しますか";
@@ -162,6 +174,12 @@ pub enum CustomError<'a> {
 
     #[error("UnsupportedContestError")]
     UnsupportedContestError,
+
+    #[error("HttpStatusError({0})")]
+    HttpStatusError(u16),
+
+    #[error("ProblemNotFoundError")]
+    ProblemNotFoundError(Cow<'a, str>),
 }
 
 impl<'a> fmt::Debug for CustomError<'a> {
@@ -180,7 +198,16 @@ impl<'a> fmt::Debug for CustomError<'a> {
             TooFewArgError => res += "引数が不足しています",
             ContestNotFoundError => res += "コンテストが見つかりませんでした",
             UnsupportedContestError => res += "対応していない種類のコンテストです",
+            HttpStatusError(_) => res += "エラーを示すステータスコードが返されました",
+            ProblemNotFoundError(problem) => {
+                res += &format!("コンテスト環境に問題が存在しませんでした({})", problem)
+            }
         }
         return write!(f, "{}", res);
     }
 }
+
+/// Box<dyn Error>はスレッド間で送れないので、並列に処理した結果のエラーは表示用の文字列にする
+pub fn thread_error(error: Box<dyn Error>) -> String {
+    return format!("{:?}", error);
+}
@@ -0,0 +1,222 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock,
+    },
+};
+
+use chrono::Local;
+use regex::Regex;
+
+use crate::{val::base_path, CustomError::*, Message};
+
+/// ログファイルの最大サイズ(バイト)
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+/// ローテーションで残す古いログファイルの数
+const MAX_LOG_FILES: u32 = 3;
+/// ログに残すレスポンスボディの最大文字数
+const MAX_BODY_LEN: usize = 4096;
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// 伏せ字にする秘匿情報のパターン
+/// 1. cookieやフォームの key=value / key:value 形式(パーセントエンコードされたものを含む)
+/// 2. AtCoderのHTMLに埋め込まれている var csrfToken = "..."
+/// 3. ログイン中のページのフォームにある <input name="csrf_token" value="...">
+static SECRET_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        r#"(?i)((?:csrf_token|csrftoken|revel_session|session_id|_session|token)(?:=|:|%3A|\s*=\s*")\s*)(?:[^;&\s"'\x00%]|%[1-9A-Fa-f][0-9A-Fa-f]|%0[1-9A-Fa-f])+"#,
+        r#"(?i)(name\s*=\s*["']?csrf_token["']?[^>]*?\bvalue\s*=\s*["']?)[^"'\s>]+"#,
+        r#"(?i)(Cookie:\s*)[^\n]+"#,
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).unwrap())
+    .collect()
+});
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Debug => write!(f, "DEBUG"),
+            Self::Info => write!(f, "INFO"),
+            Self::Warn => write!(f, "WARN"),
+            Self::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+/// `--verbose`が指定されたときに呼ぶ
+/// DEBUGレベルのログも記録し、全てのログを標準エラー出力にも表示する
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+fn is_verbose() -> bool {
+    return VERBOSE.load(Ordering::Relaxed);
+}
+
+pub fn debug(msg: &str) {
+    write(Level::Debug, msg);
+}
+
+pub fn info(msg: &str) {
+    write(Level::Info, msg);
+}
+
+pub fn warn(msg: &str) {
+    write(Level::Warn, msg);
+}
+
+pub fn error(msg: &str) {
+    write(Level::Error, msg);
+}
+
+/// ログを`{BASE_PATH}/log/ac.log`に書き込む
+/// ログの書き込みに失敗しても本来の処理は続行したいので、エラーは返さない
+pub fn write(level: Level, msg: &str) {
+    if level < Level::Info && !is_verbose() {
+        return;
+    }
+
+    let line = format!(
+        "{} [{}] {}\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+        level,
+        redact(msg)
+    );
+
+    if is_verbose() {
+        eprint!("{}", line);
+    }
+
+    if let Err(error) = append(&line) {
+        eprintln!(
+            "{} ログの書き込みに失敗しました: {}",
+            Message::Warning,
+            error
+        );
+    }
+}
+
+fn append(line: &str) -> Result<(), Box<dyn Error>> {
+    let log_dir = PathBuf::from(format!("{}/log", base_path()?));
+    if !log_dir.is_dir() {
+        fs::create_dir_all(&log_dir)?;
+    }
+
+    let log_path = log_dir.join("ac.log");
+    rotate(&log_path)?;
+
+    let mut log_file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&log_path)?;
+    log_file.write_all(line.as_bytes())?;
+
+    return Ok(());
+}
+
+/// ac.logが一定サイズを超えていたら ac.log -> ac.log.1 -> ac.log.2 ... とずらす
+fn rotate(log_path: &Path) -> Result<(), Box<dyn Error>> {
+    match fs::metadata(log_path) {
+        Ok(metadata) if metadata.len() >= MAX_LOG_SIZE => {}
+        _ => return Ok(()),
+    }
+
+    let rotated_path = |i: u32| PathBuf::from(format!("{}.{}", log_path.display(), i));
+
+    for i in (1..MAX_LOG_FILES).rev() {
+        let from = rotated_path(i);
+        if from.is_file() {
+            fs::rename(&from, rotated_path(i + 1))?;
+        }
+    }
+    fs::rename(log_path, rotated_path(1))?;
+
+    return Ok(());
+}
+
+/// トークンやセッションクッキーなどの秘匿情報を伏せ字にする
+pub fn redact(text: &str) -> String {
+    let mut res = text.to_string();
+    for re in SECRET_PATTERNS.iter() {
+        res = re.replace_all(&res, "${1}[REDACTED]").into_owned();
+    }
+
+    return res;
+}
+
+/// HTTPリクエストの結果をログに記録する
+/// ステータスコードがエラーの場合はレスポンスボディも記録し、HttpStatusErrorを返す
+pub fn request(
+    method: &str,
+    url: &str,
+    result: Result<ureq::Response, ureq::Error>,
+) -> Result<ureq::Response, Box<dyn Error>> {
+    match result {
+        Ok(response) => {
+            debug(&format!("{} {} -> {}", method, url, response.status()));
+            return Ok(response);
+        }
+        Err(ureq::Error::Status(code, response)) => {
+            let status_text = response.status_text().to_string();
+            let body = response
+                .into_string()
+                .unwrap_or_else(|error| format!("<failed to read body: {}>", error));
+            let body: String = body.chars().take(MAX_BODY_LEN).collect();
+
+            error(&format!(
+                "{} {} -> {} {}\n{}",
+                method, url, code, status_text, body
+            ));
+            return Err(Box::new(HttpStatusError(code)));
+        }
+        Err(error) => {
+            self::error(&format!("{} {} -> {}", method, url, error));
+            return Err(Box::new(error));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::log::redact;
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("REVEL_SESSION=abc123; path=/"),
+            "REVEL_SESSION=[REDACTED]; path=/"
+        );
+        assert_eq!(
+            redact("csrf_token%3AAbC%2B%3D%00"),
+            "csrf_token%3A[REDACTED]%00"
+        );
+        assert_eq!(
+            redact(r#"var csrfToken = "xyz+/=""#),
+            r#"var csrfToken = "[REDACTED]""#
+        );
+        assert_eq!(
+            redact(r#"<input type="hidden" name="csrf_token" value="AbC+/="/>"#),
+            r#"<input type="hidden" name="csrf_token" value="[REDACTED]"/>"#
+        );
+        assert_eq!(
+            redact(r#"<input name="csrf_token" type="hidden" value='AbC+/='>"#),
+            r#"<input name="csrf_token" type="hidden" value='[REDACTED]'>"#
+        );
+        assert_eq!(redact("status code: 429"), "status code: 429");
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::Path,
+    process,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::{log, val::base_path, CustomError::*};
+
+/// problem-models.jsonを取得し直すまでの期間
+const MODELS_CACHE_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 読み込んだproblem-models.json
+/// 複数の問題の情報を並列に取得する場合も、ダウンロードと読み込みは一度だけ行う
+static MODELS: Mutex<Option<HashMap<String, ProblemModel>>> = Mutex::new(None);
+
+/// 問題のメタデータ
+/// テストケースのディレクトリの隣に`{テストケースのパス}.meta.json`として保存する
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Meta {
+    pub title: Option<String>,
+    /// 実行時間制限(ミリ秒)
+    pub time_limit_ms: Option<u64>,
+    /// メモリ制限(MB)
+    pub memory_limit_mb: Option<u64>,
+    pub score: Option<u64>,
+    /// AtCoder Problemsの推定難易度(AtCoderのみ、get_with_difficultyで取得する)
+    pub difficulty: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ProblemModel {
+    difficulty: Option<f64>,
+}
+
+fn meta_path(testcase_path_str: &str) -> String {
+    return format!("{}.meta.json", testcase_path_str);
+}
+
+/// 保存されているメタデータを読み込む
+pub fn load(testcase_path_str: &str) -> Option<Meta> {
+    let json = fs::read_to_string(meta_path(testcase_path_str)).ok()?;
+    return serde_json::from_str(&json).ok();
+}
+
+pub fn save(testcase_path_str: &str, meta: &Meta) -> Result<(), Box<dyn Error>> {
+    let path = meta_path(testcase_path_str);
+    if let Some(parent) = Path::new(&path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(meta)?)?;
+
+    return Ok(());
+}
+
+/// 保存されていればそれを、なければ取得して保存したものを返す
+pub fn get(testcase_path_str: &str, problem_url: &str) -> Result<Meta, Box<dyn Error>> {
+    if let Some(meta) = load(testcase_path_str) {
+        return Ok(meta);
+    }
+
+    let meta = fetch(problem_url)?;
+    save(testcase_path_str, &meta)?;
+
+    return Ok(meta);
+}
+
+/// getと同じだが、難易度が保存されていなければ取得する
+/// コンテスト中は難易度が推定されていないので、後から表示する場合に使う
+pub fn get_with_difficulty(
+    testcase_path_str: &str,
+    problem_url: &str,
+) -> Result<Meta, Box<dyn Error>> {
+    let mut meta = get(testcase_path_str, problem_url)?;
+    if meta.difficulty.is_none() && problem_url.contains("atcoder.jp") {
+        meta.difficulty = atcoder_difficulty(problem_url);
+        if meta.difficulty.is_some() {
+            save(testcase_path_str, &meta)?;
+        }
+    }
+
+    return Ok(meta);
+}
+
+/// 問題ページからメタデータを取得する
+pub fn fetch(problem_url: &str) -> Result<Meta, Box<dyn Error>> {
+    let html = log::request("GET", problem_url, ureq::get(problem_url).call())?.into_string()?;
+    let doc = Html::parse_document(&html);
+
+    let mut meta;
+    if problem_url.contains("atcoder.jp") {
+        meta = parse_limits(
+            &select_text(&doc, "#main-container p", "Limit")
+                .or_else(|_| select_text(&doc, "#main-container p", "制限"))?,
+        )?;
+
+        let title_selector = Selector::parse("span.h2")?;
+        meta.title = doc
+            .select(&title_selector)
+            .next()
+            .and_then(|title| title.text().next())
+            .map(|title| title.trim().to_string());
+
+        // 例: 配点 : 100 点 / Score : 100 points
+        let statement = select_text(&doc, "#task-statement", "")?;
+        let re = Regex::new(r"(?:配点|Score)\s*:\s*(\d+)")?;
+        meta.score = re
+            .captures(&statement)
+            .and_then(|caps| caps[1].parse().ok());
+    } else if problem_url.contains("codeforces.com") {
+        // 例: time limit per test2 seconds / memory limit per test256 megabytes
+        meta = parse_limits(&select_text(&doc, ".problem-statement .header", "")?)?;
+        meta.title = Some(select_text(&doc, ".problem-statement .header .title", "")?);
+    } else {
+        // 例: 実行時間制限 : 1ケース 2.000秒 / メモリ制限 : 512 MB
+        meta = parse_limits(&select_text(&doc, "#content", "")?)?;
+        meta.title = Some(select_text(&doc, "#content h3", "")?);
+    }
+
+    return Ok(meta);
+}
+
+/// selectorに一致する要素のうち、containsを含む最初の要素のテキスト
+fn select_text(doc: &Html, selector: &str, contains: &str) -> Result<String, Box<dyn Error>> {
+    let selector = Selector::parse(selector).map_err(|_| HtmlError)?;
+    let text = doc
+        .select(&selector)
+        .map(|element| element.text().collect::<String>())
+        .find(|text| text.contains(contains))
+        .ok_or(HtmlError)?;
+
+    return Ok(text.split_whitespace().collect::<Vec<&str>>().join(" "));
+}
+
+/// 制限が書かれたテキストから実行時間制限とメモリ制限を取り出す
+pub fn parse_limits(text: &str) -> Result<Meta, Box<dyn Error>> {
+    let time = Regex::new(r"([0-9]+(?:\.[0-9]+)?)\s*(ms|sec|seconds?|秒)")?;
+    let memory = Regex::new(r"([0-9]+)\s*(MB|MiB|megabytes)")?;
+
+    let time_limit_ms = time.captures(text).and_then(|caps| {
+        let val: f64 = caps[1].parse().ok()?;
+        if &caps[2] == "ms" {
+            return Some(val as u64);
+        }
+        return Some((val * 1000.0).round() as u64);
+    });
+    let memory_limit_mb = memory.captures(text).and_then(|caps| caps[1].parse().ok());
+
+    return Ok(Meta {
+        time_limit_ms,
+        memory_limit_mb,
+        ..Default::default()
+    });
+}
+
+/// AtCoder Problemsの推定難易度
+/// 取得に失敗した場合は警告を記録してNone
+fn atcoder_difficulty(problem_url: &str) -> Option<i64> {
+    let task_id = problem_url.rsplit('/').next().unwrap_or_default();
+    return difficulty(task_id).unwrap_or_else(|error| {
+        log::warn(&format!("難易度の取得に失敗しました: {}", error));
+        None
+    });
+}
+
+fn difficulty(task_id: &str) -> Result<Option<i64>, Box<dyn Error>> {
+    // 他のスレッドが読み込み中の場合は、それが終わるのを待つ
+    let mut models = MODELS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if models.is_none() {
+        *models = Some(load_models()?);
+    }
+
+    return Ok(models
+        .as_ref()
+        .and_then(|models| models.get(task_id))
+        .and_then(|model| model.difficulty)
+        .map(clip_difficulty));
+}
+
+/// problem-models.jsonは大きいので`{BASE_PATH}/cache`にキャッシュする
+fn load_models() -> Result<HashMap<String, ProblemModel>, Box<dyn Error>> {
+    let cache_dir = format!("{}/cache", base_path()?);
+    let cache_path = format!("{}/problem-models.json", cache_dir);
+
+    let is_fresh = fs::metadata(&cache_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|elapsed| elapsed < MODELS_CACHE_DURATION);
+
+    if is_fresh {
+        if let Some(models) = fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+        {
+            return Ok(models);
+        }
+    }
+
+    let url = "https://kenkoooo.com/atcoder/resources/problem-models.json";
+    let json = log::request("GET", url, ureq::get(url).call())?.into_string()?;
+    let models = serde_json::from_str(&json)?;
+
+    // 他のプロセスが書きかけのファイルを読まないように、別名で書き込んでから置き換える
+    fs::create_dir_all(&cache_dir)?;
+    let tmp_path = format!("{}.{}.tmp", cache_path, process::id());
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, &cache_path)?;
+
+    return Ok(models);
+}
+
+/// AtCoder Problemsと同様に、低い難易度を0以上になるように補正する
+fn clip_difficulty(difficulty: f64) -> i64 {
+    if difficulty >= 400.0 {
+        return difficulty.round() as i64;
+    }
+    return (400.0 / (1.0 - difficulty / 400.0).exp()).round() as i64;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::meta::{clip_difficulty, parse_limits};
+
+    #[test]
+    fn test_parse_limits() {
+        let meta = parse_limits("実行時間制限: 2 sec / メモリ制限: 1024 MB").unwrap();
+        assert_eq!(meta.time_limit_ms, Some(2000));
+        assert_eq!(meta.memory_limit_mb, Some(1024));
+
+        let meta =
+            parse_limits("time limit per test 2.5 seconds memory limit per test 256 megabytes")
+                .unwrap();
+        assert_eq!(meta.time_limit_ms, Some(2500));
+        assert_eq!(meta.memory_limit_mb, Some(256));
+    }
+
+    #[test]
+    fn test_clip_difficulty() {
+        assert_eq!(clip_difficulty(1200.4), 1200);
+        assert_eq!(clip_difficulty(400.0), 400);
+        assert!(clip_difficulty(-1000.0) > 0);
+    }
+}
@@ -0,0 +1,560 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::LazyLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use colored::Colorize;
+use rayon::{
+    iter::{IntoParallelRefIterator, ParallelIterator},
+    ThreadPoolBuilder,
+};
+use regex::Regex;
+
+use crate::{
+    log,
+    sanitizer::{self, Report},
+    thread_error,
+    val::base_path,
+    CustomError::*,
+    Message,
+};
+
+/// 入出力を表示するときの最大行数
+const MAX_DISPLAY_LINES: usize = 20;
+/// 実行時間制限の何倍で強制終了するか
+/// サニタイザ付きのビルドは遅いので、制限を少し超えただけのケースは最後まで実行する
+const KILL_FACTOR: u32 = 3;
+
+/// 小数(例: 0.500000)
+static FLOAT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[+-]?[0-9]+\.[0-9]+$").unwrap());
+
+/// テストケース(`{name}.in`と`{name}.out`)
+#[derive(Debug, Clone)]
+pub struct Case {
+    /// 例: sample-1
+    pub name: String,
+    pub input: PathBuf,
+    /// 出力が用意されていないケースもある
+    pub expected: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    AC,
+    WA,
+    RE,
+    TLE,
+    MLE,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::AC => write!(f, "{}", "AC".bright_green()),
+            Self::WA => write!(f, "{}", "WA".bright_red()),
+            Self::RE => write!(f, "{}", "RE".bright_red()),
+            Self::TLE => write!(f, "{}", "TLE".bright_yellow()),
+            Self::MLE => write!(f, "{}", "MLE".bright_yellow()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub time_limit: Option<Duration>,
+    pub memory_limit_mb: Option<u64>,
+    /// 小数の出力で許容する誤差(絶対誤差または相対誤差)
+    pub error: Option<f64>,
+    /// 同時に実行するケースの数(Noneならコア数)
+    /// 実行時間を正確に測りたい場合は1にする
+    pub jobs: Option<usize>,
+}
+
+/// 1ケースの実行結果
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub case: Case,
+    pub verdict: Verdict,
+    pub elapsed: Duration,
+    /// 実行中に計測した最大のメモリ使用量(KB)
+    pub memory_kb: Option<u64>,
+    pub stdout: String,
+    pub stderr: String,
+    /// サニタイザの報告
+    pub report: Option<Report>,
+}
+
+impl Outcome {
+    /// 正解し、サニタイザの報告もない
+    pub fn passed(&self) -> bool {
+        return self.verdict == Verdict::AC && self.report.is_none();
+    }
+}
+
+/// ディレクトリ内のテストケースを番号順に集める
+pub fn cases(dir: &Path) -> Result<Vec<Case>, Box<dyn Error>> {
+    if !dir.is_dir() {
+        return Err(Box::new(DirNotfoundError(
+            dir.to_string_lossy().into_owned().into(),
+        )));
+    }
+
+    let mut res = Vec::new();
+    for file in fs::read_dir(dir)? {
+        let input = file?.path();
+        if input.extension().is_none_or(|ext| ext != "in") {
+            continue;
+        }
+        let Some(name) = input.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        let expected = input.with_extension("out");
+        res.push(Case {
+            name: name.to_string(),
+            expected: expected.is_file().then_some(expected),
+            input,
+        });
+    }
+    res.sort_by_key(|case| natural_key(&case.name));
+
+    return Ok(res);
+}
+
+/// ダウンロードしたサンプル(sample-N.in)が存在するか
+/// `ac testcase add`で追加したケース(custom-N.in)は含まない
+pub fn has_samples(dir: &Path) -> bool {
+    return sample_count(dir) > 0;
+}
+
+/// ダウンロードしたサンプルの入力ファイルの数
+pub fn sample_count(dir: &Path) -> usize {
+    return match fs::read_dir(dir) {
+        Ok(files) => files
+            .filter_map(|file| file.ok())
+            .filter(|file| {
+                let name = file.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with("sample-") && name.ends_with(".in")
+            })
+            .count(),
+        Err(_) => 0,
+    };
+}
+
+/// sample-10がsample-2より後になるように、末尾の番号を数値として比較する
+fn natural_key(name: &str) -> (String, u64) {
+    let prefix = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    return (
+        prefix.to_string(),
+        name[prefix.len()..].parse().unwrap_or_default(),
+    );
+}
+
+/// 全てのケースを実行し、結果を表示する
+/// sourceはサニタイザの報告からユーザーのコードの位置を探すためのファイル名(例: a.cpp)
+pub fn run(
+    binary: &Path,
+    source: &str,
+    cases: &[Case],
+    options: &Options,
+) -> Result<Vec<Outcome>, Box<dyn Error>> {
+    let outcomes = run_cases(binary, source, cases, options)?;
+    for outcome in &outcomes {
+        print(outcome)?;
+    }
+
+    return Ok(outcomes);
+}
+
+/// 全てのケースを並列に実行する
+/// 表示が混ざらないように、結果はまとめてケースの順に返す
+pub fn run_cases(
+    binary: &Path,
+    source: &str,
+    cases: &[Case],
+    options: &Options,
+) -> Result<Vec<Outcome>, Box<dyn Error>> {
+    // 0ならrayonのデフォルト(コア数)
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or(0))
+        .build()?;
+
+    let results: Vec<Result<Outcome, String>> = pool.install(|| {
+        cases
+            .par_iter()
+            .map(|case| run_case(binary, source, case, options).map_err(thread_error))
+            .collect()
+    });
+
+    let mut res = Vec::new();
+    for result in results {
+        res.push(result?);
+    }
+
+    return Ok(res);
+}
+
+pub fn run_case(
+    binary: &Path,
+    source: &str,
+    case: &Case,
+    options: &Options,
+) -> Result<Outcome, Box<dyn Error>> {
+    let execution = execute(binary, &case.input, options.time_limit)?;
+    let report = sanitizer::parse(&execution.stderr, source);
+
+    let verdict = if execution.timed_out
+        || options
+            .time_limit
+            .is_some_and(|limit| execution.elapsed > limit)
+    {
+        Verdict::TLE
+    } else if !execution.success {
+        Verdict::RE
+    } else if options
+        .memory_limit_mb
+        .zip(execution.memory_kb)
+        .is_some_and(|(limit, used)| used > limit * 1024)
+    {
+        Verdict::MLE
+    } else {
+        match &case.expected {
+            Some(expected) => {
+                if compare(
+                    &execution.stdout,
+                    &fs::read_to_string(expected)?,
+                    options.error,
+                ) {
+                    Verdict::AC
+                } else {
+                    Verdict::WA
+                }
+            }
+            None => Verdict::AC,
+        }
+    };
+
+    return Ok(Outcome {
+        case: case.clone(),
+        verdict,
+        elapsed: execution.elapsed,
+        memory_kb: execution.memory_kb,
+        stdout: execution.stdout,
+        stderr: execution.stderr,
+        report,
+    });
+}
+
+struct Execution {
+    success: bool,
+    timed_out: bool,
+    elapsed: Duration,
+    memory_kb: Option<u64>,
+    stdout: String,
+    stderr: String,
+}
+
+/// 入力ファイルを標準入力に渡して実行する
+/// 実行時間制限のKILL_FACTOR倍を超えたら強制終了する
+fn execute(
+    binary: &Path,
+    input: &Path,
+    time_limit: Option<Duration>,
+) -> Result<Execution, Box<dyn Error>> {
+    let start = Instant::now();
+    let mut child = Command::new(binary)
+        .stdin(File::open(input)?)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // パイプが詰まらないように、終了を待つ間も読み続ける
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let mut timed_out = false;
+    let mut memory_kb = None;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Some(kb) = peak_memory_kb(child.id()) {
+            memory_kb = Some(kb);
+        }
+        if time_limit.is_some_and(|limit| start.elapsed() > limit * KILL_FACTOR) {
+            child.kill()?;
+            timed_out = true;
+            break child.wait()?;
+        }
+        thread::sleep(Duration::from_millis(1));
+    };
+    let elapsed = start.elapsed();
+
+    return Ok(Execution {
+        success: status.success(),
+        timed_out,
+        elapsed,
+        memory_kb,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    });
+}
+
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+    return thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).into_owned()
+    });
+}
+
+/// /proc/{pid}/statusのVmHWM(実行中の最大使用量)
+/// 終了直前の使用量は取れないので、おおよその値になる
+fn peak_memory_kb(pid: u32) -> Option<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    return line.split_whitespace().nth(1)?.parse().ok();
+}
+
+/// 期待される出力の最初の値が小数なら、許容する誤差
+pub fn float_error(expected: &str) -> Option<f64> {
+    return expected
+        .split_whitespace()
+        .next()
+        .filter(|value| FLOAT.is_match(value))
+        .map(|_| 1e-6);
+}
+
+/// 空白区切りで比較する
+/// 誤差が指定されていれば、両方が数値の場合は誤差を許容する
+pub fn compare(output: &str, expected: &str, error: Option<f64>) -> bool {
+    let output: Vec<&str> = output.split_whitespace().collect();
+    let expected: Vec<&str> = expected.split_whitespace().collect();
+    if output.len() != expected.len() {
+        return false;
+    }
+
+    return output.iter().zip(&expected).all(|(a, b)| {
+        if a == b {
+            return true;
+        }
+        match (error, a.parse::<f64>(), b.parse::<f64>()) {
+            (Some(error), Ok(a), Ok(b)) => (a - b).abs() <= error * b.abs().max(1.0),
+            _ => false,
+        }
+    });
+}
+
+/// 1ケースの結果を表示する
+pub fn print(outcome: &Outcome) -> Result<(), Box<dyn Error>> {
+    let memory = outcome
+        .memory_kb
+        .map_or(String::new(), |kb| format!(" {}MB", kb / 1024));
+    println!(
+        "{} {} {} {}ms{}",
+        if outcome.passed() {
+            Message::Success
+        } else {
+            Message::Failed
+        },
+        outcome.case.name.bold(),
+        outcome.verdict,
+        outcome.elapsed.as_millis(),
+        memory
+    );
+
+    // サニタイザの報告は長いので要約だけ表示し、全文は保存する
+    if let Some(report) = &outcome.report {
+        println!(
+            "{} {}{}",
+            Message::Warning,
+            report.kind.bold(),
+            report
+                .frame
+                .as_ref()
+                .map_or(String::new(), |frame| format!(" ({})", frame))
+        );
+        save_report(outcome);
+    }
+
+    match outcome.verdict {
+        Verdict::WA => {
+            print_section("入力", &fs::read_to_string(&outcome.case.input)?);
+            print_section("出力", &outcome.stdout);
+            if let Some(expected) = &outcome.case.expected {
+                print_section("期待される出力", &fs::read_to_string(expected)?);
+            }
+        }
+        Verdict::RE if outcome.report.is_none() => {
+            print_section("標準エラー出力", &outcome.stderr);
+        }
+        Verdict::AC if outcome.case.expected.is_none() => {
+            print_section("出力", &outcome.stdout);
+        }
+        _ => {}
+    }
+
+    return Ok(());
+}
+
+/// 出力と期待される出力を並べて表示する
+/// 一致しない行は赤色にする
+pub fn print_comparison(outcome: &Outcome) -> Result<(), Box<dyn Error>> {
+    let expected = match &outcome.case.expected {
+        Some(expected) => fs::read_to_string(expected)?,
+        None => {
+            print_section("出力", &outcome.stdout);
+            return Ok(());
+        }
+    };
+
+    let output: Vec<&str> = outcome.stdout.lines().collect();
+    let expected: Vec<&str> = expected.lines().collect();
+    let width = output
+        .iter()
+        .chain(&["出力"])
+        .map(|line| display_width(line))
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "{}",
+        format!("{} | {}", pad("出力", width), "期待される出力").bright_black()
+    );
+    let len = output.len().max(expected.len());
+    for i in 0..len.min(MAX_DISPLAY_LINES) {
+        let left = output.get(i).copied().unwrap_or_default();
+        let right = expected.get(i).copied().unwrap_or_default();
+        let line = format!("{} | {}", pad(left, width), right);
+        if left.split_whitespace().eq(right.split_whitespace()) {
+            println!("{}", line);
+        } else {
+            println!("{}", line.red());
+        }
+    }
+    if len > MAX_DISPLAY_LINES {
+        println!(
+            "{}",
+            format!("... (他{}行)", len - MAX_DISPLAY_LINES).bright_black()
+        );
+    }
+
+    return Ok(());
+}
+
+/// 端末上の幅(全角文字は2として数える)
+fn display_width(text: &str) -> usize {
+    return text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum();
+}
+
+fn pad(text: &str, width: usize) -> String {
+    return format!(
+        "{}{}",
+        text,
+        " ".repeat(width.saturating_sub(display_width(text)))
+    );
+}
+
+pub fn print_section(title: &str, text: &str) {
+    println!("{}", title.bright_black());
+    let lines: Vec<&str> = text.lines().collect();
+    for line in lines.iter().take(MAX_DISPLAY_LINES) {
+        println!("{}", line);
+    }
+    if lines.len() > MAX_DISPLAY_LINES {
+        println!(
+            "{}",
+            format!("... (他{}行)", lines.len() - MAX_DISPLAY_LINES).bright_black()
+        );
+    }
+}
+
+/// `{BASE_PATH}/log/sanitizer.log`に保存する(直近のもののみ)
+fn save_report(outcome: &Outcome) {
+    let path = match base_path() {
+        Ok(base_path) => PathBuf::from(format!("{}/log/sanitizer.log", base_path)),
+        Err(_) => return,
+    };
+    let text = format!("{}\n{}", outcome.case.name, outcome.stderr);
+    match path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, text))
+    {
+        Ok(_) => println!(
+            "{} 報告の全文は{}に保存されています",
+            Message::Info,
+            path.display()
+        ),
+        Err(error) => log::warn(&format!(
+            "サニタイザの報告の保存に失敗しました: {:?}",
+            error
+        )),
+    }
+}
+
+/// 全体の結果を表示し、全て正解したかを返す
+pub fn summary(outcomes: &[Outcome]) -> bool {
+    let failed: Vec<String> = outcomes
+        .iter()
+        .filter(|outcome| !outcome.passed())
+        .map(|outcome| match &outcome.report {
+            Some(report) => format!("{}: {}", outcome.case.name, report.short_kind()),
+            None => format!("{}: {}", outcome.case.name, outcome.verdict),
+        })
+        .collect();
+
+    if failed.is_empty() {
+        println!(
+            "{} 全てのケース({}件)に正解しました",
+            Message::Success,
+            outcomes.len()
+        );
+        return true;
+    }
+
+    println!(
+        "{} {}件中{}件が不正解でした({})",
+        Message::Failed,
+        outcomes.len(),
+        failed.len(),
+        failed.join(", ")
+    );
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runner::{compare, float_error, natural_key, pad};
+
+    #[test]
+    fn test_compare() {
+        assert!(compare("1 2\n3\n", "1 2 3", None));
+        assert!(!compare("1 2", "1 2 3", None));
+        assert!(!compare("0.3333333", "0.33333333", None));
+        assert!(compare("0.3333333", "0.33333333", Some(1e-6)));
+        assert!(!compare("Yes", "yes", Some(1e-6)));
+
+        assert_eq!(float_error("0.5\n"), Some(1e-6));
+        assert_eq!(float_error("5 0.5\n"), None);
+        assert_eq!(float_error(""), None);
+        assert_eq!(pad("出力", 6), "出力  ");
+    }
+
+    #[test]
+    fn test_natural_key() {
+        let mut names = vec!["sample-10", "sample-2", "custom-1", "sample-1"];
+        names.sort_by_key(|name| natural_key(name));
+        assert_eq!(names, vec!["custom-1", "sample-1", "sample-2", "sample-10"]);
+    }
+}
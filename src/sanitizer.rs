@@ -0,0 +1,121 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// ==123==ERROR: AddressSanitizer: heap-buffer-overflow on address ...
+static ASAN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"==\d+==ERROR: (\w+Sanitizer): ([\w-]+)").unwrap());
+/// a.cpp:5:10: runtime error: signed integer overflow: ...
+static UBSAN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?:\d+:\d+): runtime error: (.*)$").unwrap());
+/// #0 0x55d4c0b2e1a9 in main /tmp/abc350/a.cpp:5:10
+static FRAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*#\d+ 0x[0-9a-f]+ in (.+) (\S+:\d+(?::\d+)?)$").unwrap());
+
+/// AddressSanitizer/UndefinedBehaviorSanitizerの報告の要約
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// 例: AddressSanitizer: heap-buffer-overflow
+    pub kind: String,
+    /// ユーザーのソース内で最初に現れるスタックフレーム(例: main at a.cpp:5:10)
+    pub frame: Option<String>,
+}
+
+impl Report {
+    /// 値などの詳細を除いた種類(例: UndefinedBehaviorSanitizer: signed integer overflow)
+    pub fn short_kind(&self) -> &str {
+        match self.kind.match_indices(':').nth(1) {
+            Some((index, _)) => return &self.kind[..index],
+            None => return &self.kind,
+        }
+    }
+}
+
+/// 標準エラー出力から最初の報告を取り出す
+/// sourceはコンパイルしたファイル名(例: a.cpp)
+pub fn parse(stderr: &str, source: &str) -> Option<Report> {
+    let mut lines = stderr.lines();
+    while let Some(line) = lines.next() {
+        if let Some(caps) = ASAN.captures(line) {
+            let kind = format!("{}: {}", &caps[1], &caps[2]);
+            // 報告の残りの部分からユーザーのソース内のフレームを探す
+            let frame = lines
+                .by_ref()
+                .take_while(|line| !line.starts_with("SUMMARY:"))
+                .filter_map(|line| FRAME.captures(line))
+                .find(|caps| is_user_file(&caps[2], source))
+                .map(|caps| format!("{} at {}", &caps[1], short_path(&caps[2])));
+
+            return Some(Report { kind, frame });
+        }
+
+        if let Some(caps) = UBSAN.captures(line) {
+            let kind = format!("UndefinedBehaviorSanitizer: {}", &caps[2]);
+            let frame = if is_user_file(&caps[1], source) {
+                Some(short_path(&caps[1]).to_string())
+            } else {
+                None
+            };
+
+            return Some(Report { kind, frame });
+        }
+    }
+
+    return None;
+}
+
+/// `path:line(:col)`がユーザーのソース内か
+fn is_user_file(location: &str, source: &str) -> bool {
+    let path = location.split(':').next().unwrap_or_default();
+    return path == source || path.ends_with(&format!("/{}", source));
+}
+
+/// ディレクトリを除いた`file:line:col`
+fn short_path(location: &str) -> &str {
+    return location.rsplit('/').next().unwrap_or(location);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sanitizer::{parse, Report};
+
+    #[test]
+    fn test_parse() {
+        let stderr = "\
+=================================================================
+==4242==ERROR: AddressSanitizer: heap-buffer-overflow on address 0x602000000020 at pc 0x55d4 bp 0x7ffd sp 0x7ffd
+READ of size 4 at 0x602000000020 thread T0
+    #0 0x55d4c0b2e1a9 in std::vector<int>::operator[](unsigned long) /usr/include/c++/12/bits/stl_vector.h:1123:25
+    #1 0x55d4c0b2d3f0 in main /tmp/abc350/a.cpp:5:10
+    #2 0x7f0a1c229d8f in __libc_start_call_main ../sysdeps/nptl/libc_start_call_main.h:58
+SUMMARY: AddressSanitizer: heap-buffer-overflow /tmp/abc350/a.cpp:5:10 in main
+";
+        assert_eq!(
+            parse(stderr, "a.cpp"),
+            Some(Report {
+                kind: String::from("AddressSanitizer: heap-buffer-overflow"),
+                frame: Some(String::from("main at a.cpp:5:10")),
+            })
+        );
+
+        let stderr = "a.cpp:7:5: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'\n";
+        let report = parse(stderr, "a.cpp").unwrap();
+        assert!(report
+            .kind
+            .starts_with("UndefinedBehaviorSanitizer: signed integer overflow"));
+        assert_eq!(report.frame.as_deref(), Some("a.cpp:7:5"));
+        assert_eq!(
+            report.short_kind(),
+            "UndefinedBehaviorSanitizer: signed integer overflow"
+        );
+
+        assert_eq!(parse("debug: 1 2 3\n", "a.cpp"), None);
+        // 別のファイル名と区別する
+        assert_eq!(
+            parse("/tmp/ba.cpp:1:1: runtime error: x\n", "a.cpp")
+                .unwrap()
+                .frame,
+            None
+        );
+    }
+}
@@ -1,19 +1,17 @@
 use std::{
     borrow::Cow::{Borrowed, Owned},
     error::Error,
-    fs::{self, OpenOptions},
-    io::Write,
+    fs,
     path::PathBuf,
 };
 
-use chrono::Local;
 use percent_encoding::percent_decode_str;
 
-use crate::{val::base_path, CustomError::*, Message};
+use crate::{log, CustomError::*, Message};
 
 pub fn run(
-    contest_name_id: &str,
-    problem_id: &str,
+    contest_id: &str,
+    task_screen_name: &str,
     sourcecode_path: PathBuf,
     contest_type: u32,
 ) -> Result<(), Box<dyn Error>> {
@@ -57,53 +55,35 @@ pub fn run(
             })?;
 
             // POST
-            let submit_url = format!("https://atcoder.jp/contests/{}/submit", contest_name_id);
+            let submit_url = format!("https://atcoder.jp/contests/{}/submit", contest_id);
             let agent = ureq::post(&submit_url).set("Cookie", &cookie);
-            match agent.send_form(&[
-                (
-                    "data.TaskScreenName",
-                    &format!("{}_{}", contest_name_id, problem_id),
-                ),
-                ("data.LanguageId", "5028"),
-                ("sourceCode", &sourcecode),
-                ("csrf_token", token.ok_or(InvalidCookieError)?),
-            ]) {
-                Ok(_) => {}
-                Err(error) => {
-                    match &error {
-                        ureq::Error::Status(code, res) => {
-                            // issue #24: logging
-                            let mut log_file = OpenOptions::new()
-                                .append(true)
-                                .create(true)
-                                .open(format!("{}/log.txt", base_path()?))?;
-                            log_file.write_all((Local::now().to_string() + "\n").as_bytes())?;
-                            log_file.write_all((submit_url + "\n").as_bytes())?;
-                            log_file.write_all(token.ok_or(InvalidCookieError)?.as_bytes())?;
-                            log_file.write_all(
-                                format!("\nstatus code:{}\n{:?}\n\n", code, res).as_bytes(),
-                            )?;
-                        }
-                        // httpエラー以外は無視
-                        _ => {}
-                    }
-                    return Err(Box::new(error));
-                }
-            }
+            log::info(&format!("{} を提出します", task_screen_name));
+            log::request(
+                "POST",
+                &submit_url,
+                agent.send_form(&[
+                    ("data.TaskScreenName", task_screen_name),
+                    ("data.LanguageId", "5028"),
+                    ("sourceCode", &sourcecode),
+                    ("csrf_token", token.ok_or(InvalidCookieError)?),
+                ]),
+            )?;
             println!("{} 提出に成功しました", Message::Success);
         }
 
         // 提出結果を取得して開く
         {
             // 提出一覧のHTMLを取得してparse
-            let submission_list_url = format!(
-                "https://atcoder.jp/contests/{}/submissions/me",
-                contest_name_id
-            );
-            let html = ureq::get(&submission_list_url)
-                .set("Cookie", &cookie)
-                .call()?
-                .into_string()?;
+            let submission_list_url =
+                format!("https://atcoder.jp/contests/{}/submissions/me", contest_id);
+            let html = log::request(
+                "GET",
+                &submission_list_url,
+                ureq::get(&submission_list_url)
+                    .set("Cookie", &cookie)
+                    .call(),
+            )?
+            .into_string()?;
             let doc = scraper::Html::parse_document(&html);
 
             // 提出urlが書いてある要素を取り出す
@@ -131,12 +111,27 @@ pub fn run(
     return Ok(());
 }
 
+/// AtCoderの問題のURLから(コンテストID, 問題名)を取り出す
+/// 例: https://atcoder.jp/contests/abc123/tasks/abc123_a -> (abc123, abc123_a)
+pub fn atcoder_task(problem_url: &str) -> Result<(&str, &str), Box<dyn Error>> {
+    let segments: Vec<&str> = problem_url.trim_end_matches('/').split('/').collect();
+
+    let contest_index = segments
+        .iter()
+        .position(|segment| *segment == "contests")
+        .ok_or(IndexError)?;
+    let contest_id = segments.get(contest_index + 1).ok_or(IndexError)?;
+    let task_screen_name = segments.last().ok_or(IndexError)?;
+
+    return Ok((contest_id, task_screen_name));
+}
+
 fn get_cookie(domain: &str) -> Result<String, Box<dyn Error>> {
     let cookies = fs::read_to_string(format!(
         "{}/.local/share/online-judge-tools/cookie.jar",
         shellexpand::tilde("~")
     ))
-    .or_else(|_| Err(Box::new(FileNotfoundError(Borrowed("cookie.jar")))))?;
+    .map_err(|_| Box::new(FileNotfoundError(Borrowed("cookie.jar"))))?;
 
     let mut res: Option<String> = None;
     for cookie in cookies.lines() {
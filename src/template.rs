@@ -0,0 +1,177 @@
+use std::{env, error::Error, path::PathBuf};
+
+use chrono::{Local, NaiveDate};
+
+use crate::{
+    meta::Meta,
+    val::{base_path, template_path},
+};
+
+/// `{DATE}`の形式
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// テンプレートに埋め込む値
+/// テンプレート中の`{URL}`などを置き換える
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    /// ファイル名(例: a, ex)
+    pub problem: String,
+    pub url: String,
+    pub contest: String,
+    pub title: Option<String>,
+    /// 実行時間制限(ミリ秒)
+    pub time_limit_ms: Option<u64>,
+    /// メモリ制限(MB)
+    pub memory_limit_mb: Option<u64>,
+}
+
+impl Context {
+    pub fn with_meta(mut self, meta: &Meta) -> Context {
+        self.title = meta.title.clone();
+        self.time_limit_ms = meta.time_limit_ms;
+        self.memory_limit_mb = meta.memory_limit_mb;
+        return self;
+    }
+
+    /// 問題ページから取得する値を除いたもの
+    pub fn without_meta(&self) -> Context {
+        return Context {
+            problem: self.problem.clone(),
+            url: self.url.clone(),
+            contest: self.contest.clone(),
+            ..Default::default()
+        };
+    }
+}
+
+/// `{BASE_PATH}/template.{name}.cpp`を候補の順に探し、なければtemplate.cppを使う
+/// 例: ["abc", "atcoder"] -> template.abc.cpp, template.atcoder.cpp, template.cpp
+pub fn path(names: &[String]) -> Result<PathBuf, Box<dyn Error>> {
+    for name in names {
+        let path = PathBuf::from(format!("{}/template.{}.cpp", base_path()?, name));
+        if path.is_file() {
+            return Ok(path);
+        }
+    }
+
+    return template_path();
+}
+
+/// 問題ページから取得する値(Meta)を使うか
+/// 使わない場合は、問題ごとの情報の取得を省略できる
+pub fn uses_meta(template: &str) -> bool {
+    return ["{TITLE}", "{TIME_LIMIT}", "{MEMORY_LIMIT}"]
+        .iter()
+        .any(|placeholder| template.contains(placeholder));
+}
+
+pub fn render(template: &str, context: &Context) -> String {
+    return render_with_date(
+        template,
+        context,
+        &Local::now().format(DATE_FORMAT).to_string(),
+    );
+}
+
+/// contentがテンプレートをcontextで埋めたものと一致するか
+/// `{DATE}`は生成した日によって変わるので、どの日付でも一致するものとする
+pub fn is_rendered(template: &str, context: &Context, content: &str) -> bool {
+    let rendered = render_with_date(template, context, "{DATE}");
+
+    let mut rest = content;
+    for (i, piece) in rendered.split("{DATE}").enumerate() {
+        if i > 0 {
+            // 例: 2024-01-01
+            let Some(date) = rest.get(..10) else {
+                return false;
+            };
+            if NaiveDate::parse_from_str(date, DATE_FORMAT).is_err() {
+                return false;
+            }
+            rest = &rest[10..];
+        }
+        match rest.strip_prefix(piece) {
+            Some(next) => rest = next,
+            None => return false,
+        }
+    }
+
+    return rest.is_empty();
+}
+
+fn render_with_date(template: &str, context: &Context, date: &str) -> String {
+    let time_limit = context
+        .time_limit_ms
+        .map_or(String::new(), |ms| format!("{}s", ms as f64 / 1000.0));
+    let memory_limit = context
+        .memory_limit_mb
+        .map_or(String::new(), |mb| format!("{}MB", mb));
+
+    return template
+        .replace("{PROBLEM}", &context.problem)
+        .replace("{URL}", &context.url)
+        .replace("{CONTEST}", &context.contest)
+        .replace("{TITLE}", context.title.as_deref().unwrap_or_default())
+        .replace("{TIME_LIMIT}", &time_limit)
+        .replace("{MEMORY_LIMIT}", &memory_limit)
+        .replace("{DATE}", date)
+        .replace("{AUTHOR}", &env::var("AC_AUTHOR").unwrap_or_default());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::template::{is_rendered, render, uses_meta, Context};
+
+    #[test]
+    fn test_render() {
+        let context = Context {
+            problem: String::from("a"),
+            url: String::from("https://atcoder.jp/contests/abc123/tasks/abc123_a"),
+            contest: String::from("abc123"),
+            title: Some(String::from("A - Five Antennas")),
+            time_limit_ms: Some(2000),
+            memory_limit_mb: Some(1024),
+        };
+
+        assert_eq!(
+            render("// {URL}\n// {TITLE} ({TIME_LIMIT}, {MEMORY_LIMIT})", &context),
+            "// https://atcoder.jp/contests/abc123/tasks/abc123_a\n// A - Five Antennas (2s, 1024MB)"
+        );
+        // C++のブロックはそのまま残す
+        assert_eq!(
+            render("int main() {}", &context.without_meta()),
+            "int main() {}"
+        );
+        assert_eq!(render("{TITLE}", &context.without_meta()), "");
+
+        assert!(uses_meta("// {TITLE}"));
+        assert!(!uses_meta("// {URL} {DATE}"));
+    }
+
+    #[test]
+    fn test_is_rendered() {
+        let context = Context {
+            problem: String::from("a"),
+            ..Default::default()
+        };
+        let template = "// {PROBLEM} {DATE}\nint main() {}\n";
+
+        assert!(is_rendered(template, &context, &render(template, &context)));
+        // 別の日に生成したもの
+        assert!(is_rendered(
+            template,
+            &context,
+            "// a 2020-01-31\nint main() {}\n"
+        ));
+        assert!(!is_rendered(
+            template,
+            &context,
+            "// a 2020-01-31\nint main() { return 1; }\n"
+        ));
+        assert!(!is_rendered(
+            template,
+            &context,
+            "// a yesterday\nint main() {}\n"
+        ));
+    }
+}